@@ -19,7 +19,7 @@ impl ComputeShader for BlurShader {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(ComputeFields, Clone, Copy)]
 enum BlurWorkerFields {
     Image,
     ImageSize,
@@ -130,12 +130,10 @@ fn convert_to_image(image: Vec<f32>) -> Image {
                 HEIGHT,
                 image
                     .iter()
-                    .map(|&x| [(x * 255.0) as u8, (x * 255.0) as u8, (x * 255.0) as u8, 255])
-                    .flatten()
+                    .flat_map(|&x| [(x * 255.0) as u8, (x * 255.0) as u8, (x * 255.0) as u8, 255])
                     .collect::<Vec<_>>(),
             )
-            .unwrap()
-            .into(),
+            .unwrap(),
         ),
         false,
         RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
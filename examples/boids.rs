@@ -2,6 +2,9 @@
 //! For now they are stupid and just fly straight, need to fix this later on.
 //! Reimplementation of https://github.com/gfx-rs/wgpu-rs/blob/master/examples/boids/main.rs
 
+// `ShaderType`'s derive emits helper fns that newer toolchains flag as unused
+#![allow(dead_code)]
+
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 
 use bevy::{
@@ -50,7 +53,7 @@ impl ComputeShader for BoidsShader {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(ComputeFields, Clone, Copy)]
 enum BoidsWorkerFields {
     Parameters,
     DeltaTime,
@@ -107,12 +110,15 @@ fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(LogDiagnosticsPlugin::default())
-        .add_plugins(FrameTimeDiagnosticsPlugin::default())
+        .add_plugins(FrameTimeDiagnosticsPlugin)
         .add_plugins(AppComputePlugin)
         .add_plugins(AppComputeWorkerPlugin::<BoidWorker>::default())
         .insert_resource(ClearColor(Color::DARK_GRAY))
         .add_systems(Startup, setup)
-        .add_systems(Update, move_entities)
+        .add_systems(
+            Update,
+            move_entities.run_if(on_compute_complete::<BoidWorker>()),
+        )
         .run()
 }
 
@@ -157,13 +163,16 @@ fn move_entities(
     q_window: Query<&Window, With<PrimaryWindow>>,
     mut q_boid: Query<(&mut Transform, &BoidEntity), With<BoidEntity>>,
 ) {
-    if !worker.ready() {
-        return;
-    }
-
     let window = q_window.single();
 
-    let boids = worker.read_vec::<Boid>(<BoidWorker as ComputeWorker>::Fields::Destination);
+    // The swap after the pass moves the freshly written boids back to `Source`
+    let output = worker
+        .latest_output(
+            <BoidWorker as ComputeWorker>::Fields::Destination,
+            <BoidWorker as ComputeWorker>::Fields::Source,
+        )
+        .unwrap();
+    let boids = worker.read_vec::<Boid>(output);
 
     worker.write(
         <BoidWorker as ComputeWorker>::Fields::DeltaTime,
@@ -10,7 +10,7 @@ impl ComputeShader for SimpleShader {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(ComputeFields, Clone, Copy)]
 enum ComputeWorkerFields {
     Uniform,
     Values,
@@ -44,7 +44,6 @@ fn main() {
         .run();
 }
 
-
 fn test(
     mut compute_worker: ResMut<AppComputeWorker<SimpleComputeWorker>>,
     render_device: Res<RenderDevice>,
@@ -58,7 +57,7 @@ fn test(
 
     if result[0].round() >= 2.0 {
         compute_worker.add_staging(
-            &*render_device,
+            &render_device,
             <SimpleComputeWorker as ComputeWorker>::Fields::Values,
             &vec![0.0; result.len() + 1].as_slice(),
         );
@@ -10,7 +10,7 @@ impl ComputeShader for SimpleShader {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(ComputeFields, Clone, Copy)]
 enum ComputeWorkerFields {
     Values,
 }
@@ -24,7 +24,7 @@ impl ComputeShader for SecondPassShader {
 #[derive(Resource)]
 struct SimpleComputeWorker;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(ComputeFields, Clone, Copy)]
 enum ComputeWorkerFields {
     Value,
     Input,
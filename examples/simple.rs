@@ -12,7 +12,7 @@ impl ComputeShader for SimpleShader {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(ComputeFields, Clone, Copy)]
 enum ComputeWorkerFields {
     Uniform,
     Values,
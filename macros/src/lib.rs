@@ -0,0 +1,80 @@
+//! Derive macros for `bevy_app_compute`, use them through its prelude.
+
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Derive `ComputeFields` for an enum of unit variants.
+///
+/// Each variant is keyed by its name, or by `#[compute_fields(key = "...")]`.
+/// Two variants with the same key are a compile error.
+#[proc_macro_derive(ComputeFields, attributes(compute_fields))]
+pub fn derive_compute_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ComputeFields can only be derived for enums",
+        ));
+    };
+
+    let mut keys = HashMap::new();
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "ComputeFields variants can't hold data",
+            ));
+        }
+
+        let ident = &variant.ident;
+        let key = variant_key(variant)?.unwrap_or_else(|| ident.to_string());
+        if let Some(other) = keys.insert(key.clone(), ident) {
+            return Err(Error::new_spanned(
+                variant,
+                format!("key \"{key}\" is already used by `{other}`"),
+            ));
+        }
+        arms.push(quote! { Self::#ident => #key });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::bevy_app_compute::prelude::ComputeFields for #name #ty_generics #where_clause {
+            fn key(&self) -> &'static str {
+                match *self {
+                    #(#arms,)*
+                }
+            }
+        }
+    })
+}
+
+/// The key set with `#[compute_fields(key = "...")]`, if any.
+fn variant_key(variant: &syn::Variant) -> Result<Option<String>> {
+    let mut key = None;
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("compute_fields") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("key") {
+                key = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `key = \"...\"`"))
+            }
+        })?;
+    }
+    Ok(key)
+}
@@ -0,0 +1,6 @@
+//! Check the diagnostics of the derive macros.
+
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
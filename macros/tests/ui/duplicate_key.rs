@@ -0,0 +1,11 @@
+use bevy_app_compute_macros::ComputeFields;
+
+#[derive(ComputeFields)]
+enum Fields {
+    Values,
+    #[compute_fields(key = "Values")] Other,
+}
+
+fn main() {
+    let _ = (Fields::Values, Fields::Other);
+}
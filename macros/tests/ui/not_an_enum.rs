@@ -0,0 +1,8 @@
+use bevy_app_compute_macros::ComputeFields;
+
+#[derive(ComputeFields)]
+struct Fields;
+
+fn main() {
+    let _ = Fields;
+}
@@ -0,0 +1,10 @@
+use bevy_app_compute_macros::ComputeFields;
+
+#[derive(ComputeFields)]
+enum Fields {
+    Values(u32),
+}
+
+fn main() {
+    let _ = Fields::Values(0);
+}
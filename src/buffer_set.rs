@@ -0,0 +1,74 @@
+use bevy::render::{
+    render_resource::{encase::internal::WriteInto, ShaderType},
+    renderer::RenderDevice,
+};
+
+use crate::{
+    traits::{ComputeShader, ComputeWorker},
+    worker::AppComputeWorker,
+};
+
+/// The buffers of a worker being rebuilt with [`AppComputeWorker::rebuild_buffers`].
+pub struct BufferSet<'a, W: ComputeWorker> {
+    worker: &'a mut AppComputeWorker<W>,
+    render_device: &'a RenderDevice,
+}
+
+impl<'a, W: ComputeWorker> BufferSet<'a, W> {
+    pub(crate) fn new(
+        worker: &'a mut AppComputeWorker<W>,
+        render_device: &'a RenderDevice,
+    ) -> Self {
+        Self {
+            worker,
+            render_device,
+        }
+    }
+
+    /// Add a new uniform buffer, and fill it with `uniform`.
+    pub fn add_uniform<T: ShaderType + WriteInto>(
+        &mut self,
+        name: W::Fields,
+        uniform: &T,
+    ) -> &mut Self {
+        self.worker.add_uniform(self.render_device, name, uniform);
+        self
+    }
+
+    /// Add a new storage buffer, and fill it with `storage`. It will be read only.
+    pub fn add_storage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: W::Fields,
+        storage: &T,
+    ) -> &mut Self {
+        self.worker.add_storage(self.render_device, name, storage);
+        self
+    }
+
+    /// Add a new read/write storage buffer, and fill it with `storage`.
+    pub fn add_rw_storage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: W::Fields,
+        storage: &T,
+    ) -> &mut Self {
+        self.worker
+            .add_rw_storage(self.render_device, name, storage);
+        self
+    }
+
+    /// Add a new read/write storage buffer filled with `data`, along with its staging buffer.
+    pub fn add_staging<T: ShaderType + WriteInto>(
+        &mut self,
+        name: W::Fields,
+        data: &T,
+    ) -> &mut Self {
+        self.worker.add_staging(self.render_device, name, data);
+        self
+    }
+
+    /// Update the dispatch size of the pass running `S`, e.g. to match the new data.
+    pub fn set_dispatch_size<S: ComputeShader>(&mut self, dispatch_size: [u32; 3]) -> &mut Self {
+        self.worker.set_dispatch_size::<S>(dispatch_size);
+        self
+    }
+}
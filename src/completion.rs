@@ -0,0 +1,131 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use bevy::render::renderer::RenderDevice;
+use parking_lot::Mutex;
+
+use crate::error::{Error, Result};
+
+/// Tracks the GPU work of one submission: the work itself and the mapping of every staging buffer.
+pub(crate) struct Completion {
+    pending: AtomicUsize,
+    work_done: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+    senders: Vec<Sender<()>>,
+    map_error: Mutex<Option<String>>,
+}
+
+impl Completion {
+    /// Every sender of `senders` is notified once everything pending is done.
+    pub(crate) fn new(pending: usize, senders: Vec<Sender<()>>) -> Arc<Self> {
+        Arc::new(Self {
+            pending: AtomicUsize::new(pending),
+            work_done: AtomicBool::new(false),
+            waker: Mutex::new(None),
+            senders,
+            map_error: Mutex::new(None),
+        })
+    }
+
+    /// Mark one of the pending operations as done, waking the awaiting task after the last one.
+    pub(crate) fn complete_one(&self) {
+        if self.pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+            if let Some(waker) = self.waker.lock().take() {
+                waker.wake();
+            }
+            for sender in &self.senders {
+                // The receiver may be gone, nobody is waiting then
+                let _ = sender.send(());
+            }
+        }
+    }
+
+    /// Mark a mapping as done, but failed with `message`.
+    pub(crate) fn fail(&self, message: String) {
+        self.map_error.lock().get_or_insert(message);
+        self.complete_one();
+    }
+
+    /// The error of the first failed mapping, if any.
+    pub(crate) fn map_error(&self) -> Option<String> {
+        self.map_error.lock().clone()
+    }
+
+    /// Mark the submitted work itself as done, the mappings may still be pending.
+    pub(crate) fn complete_work(&self) {
+        self.work_done.store(true, Ordering::Release);
+        self.complete_one();
+    }
+
+    pub(crate) fn is_work_done(&self) -> bool {
+        self.work_done.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.pending.load(Ordering::Acquire) == 0
+    }
+
+    fn register(&self, waker: &Waker) {
+        *self.waker.lock() = Some(waker.clone());
+    }
+}
+
+/// Future resolving once a submission tracked by a [`Completion`] is done.
+///
+/// The task is woken by the callbacks of the submitted work and of the mappings, which only run
+/// while the device is polled. As nothing guarantees it is polled elsewhere, the first pending poll
+/// starts a thread waiting for the GPU, instead of waking the task over and over.
+pub(crate) enum CompletionFuture {
+    Failed(Option<Error>),
+    Pending {
+        render_device: RenderDevice,
+        completion: Arc<Completion>,
+        /// Whether the thread waiting for the GPU was started.
+        waiting: bool,
+    },
+}
+
+impl Future for CompletionFuture {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.get_mut() {
+            CompletionFuture::Failed(err) => Poll::Ready(Err(err
+                .take()
+                .expect("CompletionFuture polled after completion"))),
+            CompletionFuture::Pending {
+                render_device,
+                completion,
+                waiting,
+            } => {
+                // Registered before checking, so a completion in between still wakes the task
+                completion.register(cx.waker());
+                render_device.wgpu_device().poll(wgpu::MaintainBase::Poll);
+
+                if completion.is_done() {
+                    return Poll::Ready(match completion.map_error() {
+                        Some(message) => Err(Error::MapFailed(message)),
+                        None => Ok(()),
+                    });
+                }
+
+                if !*waiting {
+                    *waiting = true;
+                    let render_device = render_device.clone();
+                    std::thread::spawn(move || {
+                        render_device.wgpu_device().poll(wgpu::MaintainBase::Wait);
+                    });
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use bevy::render::render_resource::{
+    encase::{internal::WriteInto, StorageBuffer, UniformBuffer},
+    ShaderType,
+};
+
+use crate::error::{Error, Result};
+
+/// Encode `uniform` with the uniform address space layout (std140).
+/// `field` names the buffer in the error.
+pub(crate) fn encode_uniform<T: ShaderType + WriteInto>(
+    field: &str,
+    uniform: &T,
+) -> Result<Vec<u8>> {
+    T::assert_uniform_compat();
+    let mut buffer = UniformBuffer::new(Vec::new());
+    buffer.write::<T>(uniform).map_err(|err| Error::Encase {
+        field: field.to_owned(),
+        message: err.to_string(),
+    })?;
+    Ok(buffer.into_inner())
+}
+
+/// Encode `storage` with the storage address space layout (std430).
+/// `field` names the buffer in the error.
+pub(crate) fn encode_storage<T: ShaderType + WriteInto>(
+    field: &str,
+    storage: &T,
+) -> Result<Vec<u8>> {
+    let mut buffer = StorageBuffer::new(Vec::new());
+    buffer.write::<T>(storage).map_err(|err| Error::Encase {
+        field: field.to_owned(),
+        message: err.to_string(),
+    })?;
+    Ok(buffer.into_inner())
+}
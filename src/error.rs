@@ -1,13 +1,64 @@
+use wgpu::BufferUsages;
+
+use crate::worker::RunMode;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Errors returned by the fallible functions of this crate.
+/// Their [`Display`](std::fmt::Display) messages are meant to be shown to users.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     BufferNotFound(String),
     StagingBufferNotFound(String),
     InvalidStep(String),
     PipelinesEmpty,
     PipelineNotReady,
+    PipelineFailed(String),
     EncoderIsNone,
+    Encase {
+        field: String,
+        message: String,
+    },
+    Io(std::io::Error),
+    IncompatibleSwap(String, String),
+    DuplicateField(String),
+    AliasedScratch(String, String),
+    BindingMismatch(String, String),
+    ShaderNotFound(String),
+    UnsupportedShaderFormat(String),
+    WorkerBusy,
+    InvalidBindingSize(String, u64),
+    MisalignedStride(String, u32),
+    BufferNotMapped(String),
+    IndexOutOfBounds(String, usize),
+    InvalidGrid(String),
+    WorkgroupStorageTooLarge(String, u32, u32),
+    InvalidWorkgroupSize(String, [u32; 3]),
+    WorkgroupSizeMismatch(String, [u32; 3], [u32; 3]),
+    StepOutOfBounds(usize),
+    MapFailed(String),
+    NothingToSubmit,
+    NotReducible(String),
+    NothingToReduce(String),
+    CapacityTooSmall(String, u64, u64),
+    WrongRunMode(RunMode),
+    OutOfBounds(String, u64, u64),
+    MisalignedWrite(String, u64, u64),
+    MisalignedSize(String, u64),
+    InvalidIndirectBuffer(String, u64),
+    ImageNotFound(String),
+    TextureNotReadable(String),
+    InvalidPushConstants(String, usize),
+    NotClearable(String),
+    InvalidResize(String, u64),
+    InvalidUsage(String, BufferUsages),
+    WrongFieldKind(String, &'static str),
+    DispatchTooLarge {
+        dim: usize,
+        requested: u32,
+        max: u32,
+    },
 }
 
 impl std::error::Error for Error {}
@@ -22,7 +73,137 @@ impl std::fmt::Display for Error {
             }
             Error::InvalidStep(step) => write!(f, "Invalid step `{step}`."),
             Error::PipelineNotReady => write!(f, "Pipeline isn't ready yet."),
+            Error::PipelineFailed(message) => write!(f, "Pipeline failed to compile: {message}"),
             Error::EncoderIsNone => write!(f, "The command encoder hasn't been initialized."),
+            Error::Encase { field, message } => {
+                write!(f, "Could not convert the data of {field}: {message}")
+            }
+            Error::Io(err) => write!(f, "{err}"),
+            Error::IncompatibleSwap(a, b) => write!(
+                f,
+                "Buffers {a} and {b} can't be swapped, their sizes or usages differ."
+            ),
+            Error::DuplicateField(name) => {
+                write!(f, "Field {name} is listed more than once.")
+            }
+            Error::BindingMismatch(pipeline, message) => write!(
+                f,
+                "The fields of a pass don't match the bindings of {pipeline}: {message}"
+            ),
+            Error::InvalidBindingSize(name, size) => write!(
+                f,
+                "Can't bind {size} bytes of buffer {name}, it must be between 1 and the buffer size."
+            ),
+            Error::MisalignedStride(name, stride) => write!(
+                f,
+                "Stride {stride} of buffer {name} isn't a multiple of the device's offset alignment."
+            ),
+            Error::BufferNotMapped(name) => write!(
+                f,
+                "Staging buffer {name} isn't mapped, wait for the worker to be ready before reading it."
+            ),
+            Error::IndexOutOfBounds(name, index) => {
+                write!(f, "Index {index} is out of the bounds of buffer {name}.")
+            }
+            Error::InvalidGrid(name) => write!(
+                f,
+                "Buffer {name} can't hold the grid, it is too small or its row stride is shorter than a row."
+            ),
+            Error::WorkgroupStorageTooLarge(shader, size, limit) => write!(
+                f,
+                "Shader {shader} uses {size} bytes of workgroup storage, more than the device limit of {limit} bytes."
+            ),
+            Error::InvalidWorkgroupSize(shader, size) => write!(
+                f,
+                "The workgroup size {size:?} of shader {shader} is invalid, every dimension must be at least 1."
+            ),
+            Error::WorkgroupSizeMismatch(shader, size, declared) => write!(
+                f,
+                "The workgroup size {size:?} of shader {shader} doesn't match its @workgroup_size {declared:?}."
+            ),
+            Error::StepOutOfBounds(index) => write!(f, "There is no step at index {index}."),
+            Error::MapFailed(message) => write!(f, "Could not map a buffer for reading: {message}"),
+            Error::NothingToSubmit => write!(f, "There is no recorded work to submit."),
+            Error::NotReducible(name) => write!(
+                f,
+                "Buffer {name} can't be reduced, only storage buffers can."
+            ),
+            Error::NothingToReduce(name) => {
+                write!(f, "Buffer {name} holds no element to reduce.")
+            }
+            Error::CapacityTooSmall(name, len, capacity) => write!(
+                f,
+                "Buffer {name} holds {capacity} bytes, too few for its {len} bytes of data."
+            ),
+            Error::WrongRunMode(RunMode::Immediate) => write!(
+                f,
+                "Immediate workers can't run with execute(), use execute_now() instead."
+            ),
+            Error::WrongRunMode(run_mode) => write!(
+                f,
+                "{run_mode:?} workers can't run with execute_now(), use execute() instead."
+            ),
+            Error::OutOfBounds(name, offset, len) => write!(
+                f,
+                "Can't access {len} bytes at offset {offset} of buffer {name}, they don't fit in it."
+            ),
+            Error::MisalignedWrite(name, offset, len) => write!(
+                f,
+                "Can't write {len} bytes at offset {offset} of buffer {name}, both must be multiples of 4."
+            ),
+            Error::MisalignedSize(name, size) => write!(
+                f,
+                "Buffer {name} can't be {size} bytes, the size must be a multiple of 4."
+            ),
+            Error::InvalidIndirectBuffer(name, offset) => write!(
+                f,
+                "Buffer {name} can't hold indirect dispatch arguments at offset {offset}, it must be added with add_indirect_storage and the arguments must fit at a multiple of 4 bytes."
+            ),
+            Error::ImageNotFound(name) => write!(
+                f,
+                "Texture {name} not found, it must be added to the worker and its image must be in Assets<Image> when the worker is built."
+            ),
+            Error::TextureNotReadable(name) => write!(
+                f,
+                "Texture {name} can't be read back, it needs the COPY_SRC usage and a format with a single block size."
+            ),
+            Error::InvalidPushConstants(shader, len) => write!(
+                f,
+                "Can't set {len} bytes of push constants for shader {shader}, they must be a multiple of 4 bytes within a compute range starting at 0."
+            ),
+            Error::NotClearable(name) => write!(
+                f,
+                "Buffer {name} can't be cleared, it needs the COPY_DST usage."
+            ),
+            Error::InvalidResize(name, size) => write!(
+                f,
+                "Buffer {name} can't be resized to {size} bytes, the size must be a non-zero multiple of 4, and keeping its contents needs the COPY_SRC and COPY_DST usages."
+            ),
+            Error::DispatchTooLarge {
+                dim,
+                requested,
+                max,
+            } => write!(
+                f,
+                "Can't dispatch {requested} workgroups in dimension {dim}, the device allows at most {max}."
+            ),
+            Error::WrongFieldKind(name, kind) => {
+                write!(f, "Field {name} wasn't added as a {kind} buffer.")
+            }
+            Error::AliasedScratch(a, b) => write!(
+                f,
+                "Scratch buffers {a} and {b} share the same memory, they can't be bound in the same pass."
+            ),
+            Error::InvalidUsage(name, usage) => write!(
+                f,
+                "Buffer {name} can't have the usages {usage:?}, mapping it along with other usages needs the MAPPABLE_PRIMARY_BUFFERS feature."
+            ),
+            Error::WorkerBusy => write!(f, "The worker is still working."),
+            Error::ShaderNotFound(shader) => write!(f, "Shader {shader} not found."),
+            Error::UnsupportedShaderFormat(shader) => write!(
+                f,
+                "Shader {shader} isn't supported, only WGSL, GLSL and SPIR-V shaders are."
+            ),
         }
     }
 }
@@ -0,0 +1,76 @@
+/// The fields of a [`ComputeWorker`](crate::prelude::ComputeWorker), naming its buffers.
+///
+/// Derive it with `#[derive(ComputeFields)]` on an enum of unit variants:
+/// every variant is keyed by its name, or by `#[compute_fields(key = "...")]`,
+/// and two variants sharing a key don't compile.
+pub trait ComputeFields: Copy + Send + Sync + 'static {
+    /// The key of the buffer this field names.
+    fn key(&self) -> &'static str;
+}
+
+/// A field of a [`ComputeWorker`](crate::prelude::ComputeWorker) tagged with the kind of buffer backing it.
+///
+/// The markers are only handed out by the builder once it checked the kind of the field,
+/// e.g. with [`staging_field`](crate::prelude::AppComputeWorkerBuilder::staging_field).
+pub trait TypedField<F: ComputeFields>: Copy {
+    /// The untyped field this marker wraps.
+    fn field(self) -> F;
+}
+
+/// Fields that can be read back from the CPU.
+pub trait ReadableField<F: ComputeFields>: TypedField<F> {}
+
+/// Fields that can be written to from the CPU.
+pub trait WritableField<F: ComputeFields>: TypedField<F> {}
+
+/// A field added with `add_staging` or `add_empty_staging`, see `staging_field`.
+/// It can be both read and written.
+#[derive(Clone, Copy, Debug)]
+pub struct StagingField<F>(pub(crate) F);
+
+/// A field added with `add_uniform` or `add_empty_uniform`, see `uniform_field`.
+/// It can only be written.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformField<F>(pub(crate) F);
+
+/// A field added with `add_storage` or `add_empty_storage`, see `storage_field`.
+/// It can only be written.
+#[derive(Clone, Copy, Debug)]
+pub struct StorageField<F>(pub(crate) F);
+
+/// A field added with `add_rw_storage` or `add_empty_rw_storage`, see `rw_storage_field`.
+/// It can be both read and written, reading it goes through a temporary staging buffer.
+#[derive(Clone, Copy, Debug)]
+pub struct RwStorageField<F>(pub(crate) F);
+
+impl<F: ComputeFields> TypedField<F> for StagingField<F> {
+    fn field(self) -> F {
+        self.0
+    }
+}
+
+impl<F: ComputeFields> TypedField<F> for UniformField<F> {
+    fn field(self) -> F {
+        self.0
+    }
+}
+
+impl<F: ComputeFields> TypedField<F> for StorageField<F> {
+    fn field(self) -> F {
+        self.0
+    }
+}
+
+impl<F: ComputeFields> TypedField<F> for RwStorageField<F> {
+    fn field(self) -> F {
+        self.0
+    }
+}
+
+impl<F: ComputeFields> ReadableField<F> for StagingField<F> {}
+impl<F: ComputeFields> ReadableField<F> for RwStorageField<F> {}
+
+impl<F: ComputeFields> WritableField<F> for StagingField<F> {}
+impl<F: ComputeFields> WritableField<F> for UniformField<F> {}
+impl<F: ComputeFields> WritableField<F> for StorageField<F> {}
+impl<F: ComputeFields> WritableField<F> for RwStorageField<F> {}
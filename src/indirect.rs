@@ -0,0 +1,64 @@
+use std::borrow::Cow;
+
+use bevy::render::{
+    render_resource::{BindGroupLayout, Buffer, ComputePipeline},
+    renderer::RenderDevice,
+};
+use wgpu::{BufferDescriptor, BufferUsages, ShaderModuleDescriptor, ShaderSource};
+
+/// Turns the element count written by an earlier pass into indirect dispatch arguments,
+/// before the pass itself is dispatched indirectly.
+#[derive(Clone, Debug)]
+pub(crate) struct IndirectCount {
+    pub(crate) count: String,
+    pub(crate) args: Buffer,
+    pub(crate) pipeline: ComputePipeline,
+    pub(crate) layout: BindGroupLayout,
+}
+
+impl IndirectCount {
+    pub(crate) fn new(render_device: &RenderDevice, count: String, workgroup_size: u32) -> Self {
+        let source = format!(
+            "@group(0) @binding(0) var<storage, read> count: u32;
+@group(0) @binding(1) var<storage, read_write> args: array<u32, 3>;
+
+@compute @workgroup_size(1)
+fn main() {{
+    args[0] = (count + {workgroup_size}u - 1u) / {workgroup_size}u;
+    args[1] = 1u;
+    args[2] = 1u;
+}}"
+        );
+
+        let module = render_device
+            .wgpu_device()
+            .create_shader_module(ShaderModuleDescriptor {
+                label: Some("count_to_dispatch_args"),
+                source: ShaderSource::Wgsl(Cow::Owned(source)),
+            });
+        let pipeline =
+            render_device
+                .wgpu_device()
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("count_to_dispatch_args"),
+                    layout: None,
+                    module: &module,
+                    entry_point: "main",
+                });
+        let layout = pipeline.get_bind_group_layout(0).into();
+
+        let args = render_device.create_buffer(&BufferDescriptor {
+            label: Some(&format!("{count}_dispatch_args")),
+            size: 3 * std::mem::size_of::<u32>() as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::INDIRECT,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            count,
+            args,
+            pipeline: pipeline.into(),
+            layout,
+        }
+    }
+}
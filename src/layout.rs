@@ -0,0 +1,53 @@
+use bevy::render::render_resource::{
+    BindGroupLayoutEntry, BindingType, BufferBindingType, ShaderStages, TextureSampleType,
+    TextureViewDimension,
+};
+
+/// A uniform buffer at `@binding(binding)`, visible from compute shaders.
+/// Helper to build your own [`layouts()`](crate::prelude::ComputeShader::layouts).
+pub fn uniform_binding(binding: u32) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// A storage buffer at `@binding(binding)`, visible from compute shaders.
+/// Helper to build your own [`layouts()`](crate::prelude::ComputeShader::layouts).
+pub fn storage_binding(binding: u32, read_only: bool) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// A sampled texture at `@binding(binding)`, visible from compute shaders.
+/// Helper to build your own [`layouts()`](crate::prelude::ComputeShader::layouts).
+pub fn texture_binding(
+    binding: u32,
+    sample_type: TextureSampleType,
+    view_dimension: TextureViewDimension,
+) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Texture {
+            sample_type,
+            view_dimension,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
@@ -1,22 +1,53 @@
 #![doc = include_str!("../README.md")]
 
+mod buffer_set;
+mod completion;
+mod encoding;
 mod error;
+mod fields;
+mod indirect;
+mod layout;
+mod once;
 mod pipeline_cache;
 mod plugin;
+mod reduce;
+mod rng;
+mod scheduler;
+mod step_info;
+#[cfg(feature = "test_util")]
+pub mod test_util;
+mod timestamps;
 mod traits;
 mod worker;
 mod worker_builder;
+mod workgroup_storage;
 
 /// Helper module to import most used elements.
 pub mod prelude {
     pub use crate::{
+        buffer_set::BufferSet,
+        error::Error,
+        fields::{
+            ComputeFields, ReadableField, RwStorageField, StagingField, StorageField, TypedField,
+            UniformField, WritableField,
+        },
+        layout::{storage_binding, texture_binding, uniform_binding},
+        once::{run_compute_once, try_run_compute_once},
         pipeline_cache::AppPipelineCache,
-        plugin::{AppComputePlugin, AppComputeWorkerPlugin},
+        plugin::{
+            on_compute_complete, AppComputePlugin, AppComputeWorkerGroupPlugin,
+            AppComputeWorkerPlugin, ComputeWorkerGroup,
+        },
+        reduce::{ReduceElement, ReduceOp},
+        scheduler::{ComputeScheduler, ComputeSet},
+        step_info::StepInfo,
         traits::{ComputeShader, ComputeWorker},
-        worker::AppComputeWorker,
+        worker::{AppComputeWorker, Phase, RunMode, WorkerFinished, WorkerState},
         worker_builder::AppComputeWorkerBuilder,
     };
 
+    pub use bevy_app_compute_macros::ComputeFields;
+
     // Since these are always used when using this crate
     pub use bevy::render::render_resource::{ShaderRef, ShaderType};
 }
@@ -0,0 +1,246 @@
+use std::{borrow::Cow, ops::Range};
+
+use bevy::{
+    render::{
+        render_resource::{Buffer, Texture},
+        renderer::{RenderDevice, RenderQueue},
+    },
+    tasks::block_on,
+};
+use wgpu::{
+    util::BufferInitDescriptor, BindGroupEntry, BufferDescriptor, BufferUsages,
+    CommandEncoderDescriptor, ComputePassDescriptor, ImageCopyBuffer, ImageDataLayout,
+    ShaderModuleDescriptor, ShaderSource, TextureUsages, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::{
+    completion::Completion,
+    error::{Error, Result},
+};
+
+/// Run a WGSL compute shader once, without declaring a [`ComputeWorker`](crate::prelude::ComputeWorker).
+///
+/// Every entry of `inputs` is uploaded to a read/write storage buffer bound to
+/// `@group(0) @binding(i)`, `i` being its position in the slice.
+/// The shader entry point must be `main`.
+/// Once the GPU is done, the content of `output_field` is returned as raw bytes.
+/// This blocks until the GPU has finished.
+/// Fails with [`Error::PipelineFailed`] if the shader doesn't compile.
+pub fn try_run_compute_once(
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+    wgsl_source: &str,
+    inputs: &[(&str, &[u8])],
+    dispatch_size: [u32; 3],
+    output_field: &str,
+) -> Result<Vec<u8>> {
+    let Some(output_index) = inputs.iter().position(|(name, _)| *name == output_field) else {
+        return Err(Error::BufferNotFound(output_field.to_owned()));
+    };
+
+    // Compilation errors are reported as validation errors, not returned
+    let device = render_device.wgpu_device();
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(ShaderModuleDescriptor {
+        label: None,
+        source: ShaderSource::Wgsl(Cow::Borrowed(wgsl_source)),
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: None,
+        layout: None,
+        module: &module,
+        entry_point: "main",
+    });
+    if let Some(err) = block_on(device.pop_error_scope()) {
+        return Err(Error::PipelineFailed(err.to_string()));
+    }
+
+    let buffers: Vec<Buffer> = inputs
+        .iter()
+        .map(|(name, contents)| {
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some(name),
+                contents,
+                usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+            })
+        })
+        .collect();
+
+    let entries: Vec<BindGroupEntry> = buffers
+        .iter()
+        .enumerate()
+        .map(|(index, buffer)| BindGroupEntry {
+            binding: index as u32,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+
+    let bind_group = (!entries.is_empty()).then(|| {
+        render_device.create_bind_group(None, &pipeline.get_bind_group_layout(0).into(), &entries)
+    });
+
+    let mut encoder =
+        render_device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+    {
+        let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: None,
+            timestamp_writes: None,
+        });
+        cpass.set_pipeline(&pipeline);
+        if let Some(bind_group) = &bind_group {
+            cpass.set_bind_group(0, bind_group, &[]);
+        }
+        cpass.dispatch_workgroups(dispatch_size[0], dispatch_size[1], dispatch_size[2]);
+    }
+
+    let output = &buffers[output_index];
+    let staging = render_device.create_buffer(&BufferDescriptor {
+        label: Some(output_field),
+        size: output.size(),
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    encoder.copy_buffer_to_buffer(output, 0, &staging, 0, output.size());
+    render_queue.submit(Some(encoder.finish()));
+
+    let result = map_read_blocking(render_device, &staging);
+
+    staging.destroy();
+    for buffer in buffers {
+        buffer.destroy();
+    }
+
+    result
+}
+
+/// Run a WGSL compute shader once, see [`try_run_compute_once`].
+/// In case of error, this function will panic.
+pub fn run_compute_once(
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+    wgsl_source: &str,
+    inputs: &[(&str, &[u8])],
+    dispatch_size: [u32; 3],
+    output_field: &str,
+) -> Vec<u8> {
+    try_run_compute_once(
+        render_device,
+        render_queue,
+        wgsl_source,
+        inputs,
+        dispatch_size,
+        output_field,
+    )
+    .unwrap()
+}
+
+/// Copy `buffer` to a temporary `MAP_READ` buffer, wait for the GPU and return its content.
+/// `buffer` needs the `COPY_SRC` usage.
+pub(crate) fn read_buffer_blocking(
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+    buffer: &Buffer,
+) -> Result<Vec<u8>> {
+    read_buffer_range_blocking(render_device, render_queue, buffer, 0..buffer.size())
+}
+
+/// Copy `range` of a `COPY_SRC` buffer to a staging buffer of the same size, wait for the GPU
+/// and return the bytes. The range must be aligned to [`wgpu::COPY_BUFFER_ALIGNMENT`].
+pub(crate) fn read_buffer_range_blocking(
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+    buffer: &Buffer,
+    range: Range<u64>,
+) -> Result<Vec<u8>> {
+    let size = range.end - range.start;
+    let staging = render_device.create_buffer(&BufferDescriptor {
+        label: None,
+        size,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder =
+        render_device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+    encoder.copy_buffer_to_buffer(buffer, range.start, &staging, 0, size);
+    render_queue.submit(Some(encoder.finish()));
+
+    let bytes = map_read_blocking(render_device, &staging);
+    staging.destroy();
+    bytes
+}
+
+/// Copy every layer of the first mip level of the `COPY_SRC` texture `name` to a staging buffer,
+/// wait for the GPU and return the texels without the row padding copies need.
+/// Fails for formats without a single block size, like depth-stencil ones.
+pub(crate) fn read_texture_blocking(
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+    name: &str,
+    texture: &Texture,
+) -> Result<Vec<u8>> {
+    let size = texture.size();
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = match format.block_copy_size(None) {
+        Some(block_size) if texture.usage().contains(TextureUsages::COPY_SRC) => block_size,
+        _ => return Err(Error::TextureNotReadable(name.to_owned())),
+    };
+
+    let row_size = size.width.div_ceil(block_width) * block_size;
+    let padded_row_size = row_size.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+    let rows = size.height.div_ceil(block_height);
+    let row_count = rows * size.depth_or_array_layers;
+
+    let staging = render_device.create_buffer(&BufferDescriptor {
+        label: None,
+        size: padded_row_size as u64 * row_count as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder =
+        render_device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &staging,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_size),
+                rows_per_image: Some(rows),
+            },
+        },
+        size,
+    );
+    render_queue.submit(Some(encoder.finish()));
+
+    let padded = map_read_blocking(render_device, &staging);
+    staging.destroy();
+    Ok(padded?
+        .chunks_exact(padded_row_size as usize)
+        .flat_map(|row| &row[..row_size as usize])
+        .copied()
+        .collect())
+}
+
+/// Map a `MAP_READ` buffer, wait for the GPU and copy its content.
+pub(crate) fn map_read_blocking(render_device: &RenderDevice, buffer: &Buffer) -> Result<Vec<u8>> {
+    let completion = Completion::new(1, Vec::new());
+
+    let slice = buffer.slice(..);
+    let map_completion = completion.clone();
+    slice.map_async(wgpu::MapMode::Read, move |result| match result {
+        Ok(()) => map_completion.complete_one(),
+        Err(err) => map_completion.fail(err.to_string()),
+    });
+    render_device.wgpu_device().poll(wgpu::MaintainBase::Wait);
+
+    if let Some(message) = completion.map_error() {
+        return Err(Error::MapFailed(message));
+    }
+
+    let bytes = slice.get_mapped_range().to_vec();
+    buffer.unmap();
+    Ok(bytes)
+}
@@ -2,28 +2,79 @@ use bevy::{
     prelude::*,
     render::render_resource::{
         CachedComputePipelineId, CachedPipeline, CachedPipelineState, ComputePipeline, Pipeline,
+        PipelineCacheError,
     },
+    utils::HashMap,
 };
 
 #[derive(Resource)]
 pub struct AppPipelineCache {
     pub pipeline_cache: Vec<Option<CachedPipeline>>,
+    /// Pipelines already queued by a worker, keyed by shader type path,
+    /// so that workers sharing a shader reuse the same pipeline.
+    pub(crate) queued_pipelines: HashMap<String, CachedComputePipelineId>,
 }
 impl AppPipelineCache {
+    /// Iterate over the state of every pipeline known to the render world, with its id.
+    /// Pipelines being created are reported as `Queued`, and errors other than missing
+    /// shaders are reported as `CreateShaderModule` with the original message.
+    pub fn iter_states(&self) -> impl Iterator<Item = (usize, &CachedPipelineState)> {
+        self.pipeline_cache
+            .iter()
+            .enumerate()
+            .filter_map(|(id, pipeline)| pipeline.as_ref().map(|pipeline| (id, &pipeline.state)))
+    }
+
+    /// Like `get_compute_pipeline`, but tells a pipeline that failed to compile apart from
+    /// one still compiling: `Ok(None)` while waiting, `Err(message)` once it failed.
+    /// A pipeline waiting for its shader to load is still considered compiling.
+    #[inline]
+    pub fn get_compute_pipeline_result(
+        &self,
+        id: CachedComputePipelineId,
+    ) -> Result<Option<&ComputePipeline>, String> {
+        let Some(pipeline) = self.pipeline_cache.get(id.id()).and_then(|x| x.as_ref()) else {
+            return Ok(None);
+        };
+
+        match &pipeline.state {
+            CachedPipelineState::Ok(Pipeline::ComputePipeline(pipeline)) => Ok(Some(pipeline)),
+            CachedPipelineState::Err(
+                PipelineCacheError::ShaderNotLoaded(_)
+                | PipelineCacheError::ShaderImportNotYetAvailable,
+            ) => Ok(None),
+            CachedPipelineState::Err(err) => Err(err.to_string()),
+            _ => Ok(None),
+        }
+    }
+
+    /// The state of the pipeline `id`, if the render world knows it yet.
+    #[inline]
+    pub fn pipeline_state(&self, id: CachedComputePipelineId) -> Option<&CachedPipelineState> {
+        self.pipeline_cache
+            .get(id.id())
+            .and_then(|x| x.as_ref())
+            .map(|pipeline| &pipeline.state)
+    }
+
+    /// The error message of the pipeline `id`, if it failed to compile.
+    /// Missing shaders aren't errors, they may still be loading.
+    #[inline]
+    pub fn compilation_error(&self, id: CachedComputePipelineId) -> Option<String> {
+        self.get_compute_pipeline_result(id).err()
+    }
+
     #[inline]
     pub fn get_compute_pipeline(&self, id: CachedComputePipelineId) -> Option<&ComputePipeline> {
         self.pipeline_cache
             .get(id.id())
-            .map(|x| {
-                x.as_ref().map(|x| {
-                    if let CachedPipelineState::Ok(Pipeline::ComputePipeline(pipeline)) = &x.state {
-                        Some(pipeline)
-                    } else {
-                        None
-                    }
-                })
+            .and_then(|x| x.as_ref())
+            .and_then(|x| {
+                if let CachedPipelineState::Ok(Pipeline::ComputePipeline(pipeline)) = &x.state {
+                    Some(pipeline)
+                } else {
+                    None
+                }
             })
-            .flatten()
-            .flatten()
     }
 }
@@ -4,16 +4,19 @@ use bevy::{
     prelude::*,
     render::{
         render_resource::{
-            CachedPipeline, CachedPipelineState, Pipeline, PipelineCache, PipelineDescriptor,
+            CachedPipeline, CachedPipelineState, Pipeline, PipelineCache, PipelineCacheError,
+            PipelineDescriptor,
         },
         MainWorld, RenderApp,
     },
+    utils::HashMap,
 };
 
 use crate::{
     pipeline_cache::AppPipelineCache,
+    scheduler::{ComputeScheduler, ComputeSet},
     traits::ComputeWorker,
-    worker::{AppComputeWorker, RunMode},
+    worker::{AppComputeWorker, RunMode, WorkerFinished},
 };
 
 /// The main plugin. Always include it if you want to use `bevy_app_compute`
@@ -23,7 +26,17 @@ impl Plugin for AppComputePlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(AppPipelineCache {
             pipeline_cache: vec![],
-        });
+            queued_pipelines: HashMap::default(),
+        })
+        .init_resource::<ComputeScheduler>()
+        .configure_sets(
+            PostUpdate,
+            (ComputeSet::Record, ComputeSet::Submit, ComputeSet::Finish).chain(),
+        )
+        .add_systems(
+            PostUpdate,
+            ComputeScheduler::submit.in_set(ComputeSet::Submit),
+        );
     }
 
     fn finish(&self, app: &mut App) {
@@ -43,7 +56,17 @@ fn update_app_pipeline(pipeline_cache: Res<PipelineCache>, mut app_world: ResMut
                 Pipeline::RenderPipeline(x) => Pipeline::RenderPipeline(x.clone()),
                 Pipeline::ComputePipeline(x) => Pipeline::ComputePipeline(x.clone()),
             })),
-            _ => None,
+            // The creation task can't be cloned, it is still queued as far as the app is concerned
+            CachedPipelineState::Queued | CachedPipelineState::Creating(_) => {
+                Some(CachedPipelineState::Queued)
+            }
+            CachedPipelineState::Err(err) => Some(CachedPipelineState::Err(match err {
+                PipelineCacheError::ShaderNotLoaded(id) => PipelineCacheError::ShaderNotLoaded(*id),
+                PipelineCacheError::ShaderImportNotYetAvailable => {
+                    PipelineCacheError::ShaderImportNotYetAvailable
+                }
+                err => PipelineCacheError::CreateShaderModule(err.to_string()),
+            })),
         };
         let cloned_descriptor = match &pipeline.descriptor {
             PipelineDescriptor::RenderPipelineDescriptor(x) => {
@@ -80,18 +103,103 @@ impl<W: ComputeWorker> Plugin for AppComputeWorkerPlugin<W> {
 
     fn finish(&self, app: &mut App) {
         let worker = W::build(app);
+        app.add_event::<WorkerFinished<W>>();
+        app.sub_app_mut(RenderApp).add_systems(
+            ExtractSchedule,
+            AppComputeWorker::<W>::queue_pending_pipelines,
+        );
 
         match worker.run_mode() {
-            RunMode::Continuous | RunMode::OneShot(_) => {
+            RunMode::Continuous | RunMode::OneShot(_) | RunMode::EveryNFrames(_)
+                if worker.manual_unmap =>
+            {
                 app.add_systems(Update, AppComputeWorker::<W>::extract_pipelines)
                     .add_systems(
                         PostUpdate,
-                        (AppComputeWorker::<W>::unmap_all, AppComputeWorker::<W>::run).chain(),
+                        (
+                            AppComputeWorker::<W>::record.in_set(ComputeSet::Record),
+                            AppComputeWorker::<W>::run.in_set(ComputeSet::Finish),
+                        ),
+                    );
+            }
+            RunMode::Continuous | RunMode::OneShot(_) | RunMode::EveryNFrames(_) => {
+                app.add_systems(Update, AppComputeWorker::<W>::extract_pipelines)
+                    .add_systems(
+                        PostUpdate,
+                        (
+                            (
+                                AppComputeWorker::<W>::auto_unmap,
+                                AppComputeWorker::<W>::record,
+                            )
+                                .chain()
+                                .in_set(ComputeSet::Record),
+                            AppComputeWorker::<W>::run.in_set(ComputeSet::Finish),
+                        ),
                     );
             }
             RunMode::Immediate => {}
         }
-        if worker.run_mode() != RunMode::Immediate {}
         app.insert_resource(worker);
     }
 }
+
+/// Run condition that is true when a run of the worker `W` completed since the system last ran,
+/// e.g. `.add_systems(Update, read_results.run_if(on_compute_complete::<MyWorker>()))`.
+/// It reads the [`WorkerFinished<W>`] events, so it never fires for immediate workers.
+pub fn on_compute_complete<W: ComputeWorker>(
+) -> impl FnMut(EventReader<WorkerFinished<W>>) -> bool + Clone {
+    move |mut finished: EventReader<WorkerFinished<W>>| {
+        let completed = !finished.is_empty();
+        finished.clear();
+        completed
+    }
+}
+
+/// Several [`ComputeWorker`] types, registered together by an [`AppComputeWorkerGroupPlugin`].
+/// Implemented for tuples of up to 8 workers.
+pub trait ComputeWorkerGroup: Send + Sync + 'static {
+    /// Build the workers and add their systems, in the order of the tuple.
+    fn finish(app: &mut App);
+}
+
+macro_rules! impl_compute_worker_group {
+    ($($worker:ident),*) => {
+        impl<$($worker: ComputeWorker),*> ComputeWorkerGroup for ($($worker,)*) {
+            fn finish(app: &mut App) {
+                $(AppComputeWorkerPlugin::<$worker>::default().finish(app);)*
+            }
+        }
+    };
+}
+
+impl_compute_worker_group!(W1);
+impl_compute_worker_group!(W1, W2);
+impl_compute_worker_group!(W1, W2, W3);
+impl_compute_worker_group!(W1, W2, W3, W4);
+impl_compute_worker_group!(W1, W2, W3, W4, W5);
+impl_compute_worker_group!(W1, W2, W3, W4, W5, W6);
+impl_compute_worker_group!(W1, W2, W3, W4, W5, W6, W7);
+impl_compute_worker_group!(W1, W2, W3, W4, W5, W6, W7, W8);
+
+/// Plugin to initialise several [`AppComputeWorker<W>`] structs at once,
+/// e.g. `AppComputeWorkerGroupPlugin::<(WorkerA, WorkerB)>::default()`.
+/// Each worker gets the same systems as with its own [`AppComputeWorkerPlugin`].
+pub struct AppComputeWorkerGroupPlugin<G: ComputeWorkerGroup> {
+    _phantom: PhantomData<G>,
+}
+
+impl<G: ComputeWorkerGroup> Default for AppComputeWorkerGroupPlugin<G> {
+    fn default() -> Self {
+        Self {
+            _phantom: Default::default(),
+        }
+    }
+}
+
+impl<G: ComputeWorkerGroup> Plugin for AppComputeWorkerGroupPlugin<G> {
+    fn build(&self, _app: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        G::finish(app);
+    }
+}
@@ -0,0 +1,235 @@
+use std::borrow::Cow;
+
+use bevy::render::{
+    render_resource::{Buffer, ComputePipeline},
+    renderer::{RenderDevice, RenderQueue},
+};
+use bytemuck::{bytes_of, pod_read_unaligned, AnyBitPattern};
+use wgpu::{
+    util::BufferInitDescriptor, BindGroupEntry, BufferDescriptor, BufferUsages,
+    CommandEncoderDescriptor, ComputePassDescriptor, ShaderModuleDescriptor, ShaderSource,
+};
+
+use crate::{
+    error::{Error, Result},
+    once::read_buffer_range_blocking,
+};
+
+/// The operation of a reduction, see [`AppComputeWorker::reduce`](crate::prelude::AppComputeWorker::reduce).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ReduceOp {
+    Sum,
+    Min,
+    Max,
+}
+
+/// Element types a buffer can be reduced over.
+/// It is sealed, as the reduction shader only handles the types implementing it here.
+pub trait ReduceElement: AnyBitPattern + sealed::Sealed {
+    /// The WGSL name of the type.
+    const WGSL_TYPE: &'static str;
+
+    /// The WGSL expression of the neutral element of `op`.
+    fn wgsl_identity(op: ReduceOp) -> &'static str;
+}
+
+impl ReduceElement for f32 {
+    const WGSL_TYPE: &'static str = "f32";
+
+    fn wgsl_identity(op: ReduceOp) -> &'static str {
+        match op {
+            ReduceOp::Sum => "0.0",
+            ReduceOp::Min => "3.40282347e+38",
+            ReduceOp::Max => "-3.40282347e+38",
+        }
+    }
+}
+
+impl ReduceElement for i32 {
+    const WGSL_TYPE: &'static str = "i32";
+
+    fn wgsl_identity(op: ReduceOp) -> &'static str {
+        match op {
+            ReduceOp::Sum => "0i",
+            ReduceOp::Min => "2147483647i",
+            ReduceOp::Max => "(-2147483647i - 1i)",
+        }
+    }
+}
+
+impl ReduceElement for u32 {
+    const WGSL_TYPE: &'static str = "u32";
+
+    fn wgsl_identity(op: ReduceOp) -> &'static str {
+        match op {
+            ReduceOp::Sum | ReduceOp::Max => "0u",
+            ReduceOp::Min => "4294967295u",
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for f32 {}
+    impl Sealed for i32 {}
+    impl Sealed for u32 {}
+}
+
+const WORKGROUP_SIZE: u32 = 256;
+const MAX_WORKGROUPS: u32 = 65535;
+
+/// Create the pipeline reducing a buffer of `T` with `op`.
+/// Every workgroup reduces its part of the input to one element of the output,
+/// so reducing a whole buffer takes a few passes.
+pub(crate) fn reduce_pipeline<T: ReduceElement>(
+    render_device: &RenderDevice,
+    op: ReduceOp,
+) -> ComputePipeline {
+    let combine = match op {
+        ReduceOp::Sum => "a + b",
+        ReduceOp::Min => "min(a, b)",
+        ReduceOp::Max => "max(a, b)",
+    };
+    let source = format!(
+        "@group(0) @binding(0) var<storage, read> input: array<{ty}>;
+@group(0) @binding(1) var<storage, read_write> output: array<{ty}>;
+@group(0) @binding(2) var<uniform> params: vec4<u32>;
+
+var<workgroup> partial: array<{ty}, {WORKGROUP_SIZE}>;
+
+fn combine(a: {ty}, b: {ty}) -> {ty} {{
+    return {combine};
+}}
+
+@compute @workgroup_size({WORKGROUP_SIZE})
+fn main(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {{
+    var accumulator: {ty} = {identity};
+    for (var i = group.x * {WORKGROUP_SIZE}u + local; i < params.x; i += groups.x * {WORKGROUP_SIZE}u) {{
+        accumulator = combine(accumulator, input[i]);
+    }}
+    partial[local] = accumulator;
+    workgroupBarrier();
+
+    for (var stride = {WORKGROUP_SIZE}u / 2u; stride > 0u; stride /= 2u) {{
+        if local < stride {{
+            partial[local] = combine(partial[local], partial[local + stride]);
+        }}
+        workgroupBarrier();
+    }}
+
+    if local == 0u {{
+        output[group.x] = partial[0];
+    }}
+}}",
+        ty = T::WGSL_TYPE,
+        identity = T::wgsl_identity(op),
+    );
+
+    let module = render_device
+        .wgpu_device()
+        .create_shader_module(ShaderModuleDescriptor {
+            label: Some("reduce"),
+            source: ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+    render_device
+        .wgpu_device()
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("reduce"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        })
+        .into()
+}
+
+/// Reduce every element of `buffer`, a storage buffer of `T`, with `pipeline`.
+/// This blocks until the GPU is done.
+pub(crate) fn reduce_blocking<T: ReduceElement>(
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+    pipeline: &ComputePipeline,
+    buffer: &Buffer,
+) -> Result<T> {
+    let element_size = std::mem::size_of::<T>() as u64;
+    let layout = pipeline.get_bind_group_layout(0).into();
+
+    let mut encoder =
+        render_device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+    let mut input = buffer.clone();
+    let mut count = (buffer.size() / element_size) as u32;
+    let mut temp_buffers = vec![];
+    loop {
+        let groups = count.div_ceil(WORKGROUP_SIZE).clamp(1, MAX_WORKGROUPS);
+
+        let output = render_device.create_buffer(&BufferDescriptor {
+            label: Some("reduce_output"),
+            size: groups as u64 * element_size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let params = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("reduce_params"),
+            contents: bytes_of(&[count, 0, 0, 0]),
+            usage: BufferUsages::UNIFORM,
+        });
+        let bind_group = render_device.create_bind_group(
+            None,
+            &layout,
+            &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: input.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: output.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        );
+
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("reduce"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.dispatch_workgroups(groups, 1, 1);
+        }
+
+        temp_buffers.push(params);
+        temp_buffers.push(output.clone());
+        input = output;
+        count = groups;
+        if groups == 1 {
+            break;
+        }
+    }
+    render_queue.submit(Some(encoder.finish()));
+
+    let bytes = read_buffer_range_blocking(render_device, render_queue, &input, 0..element_size);
+    for temp_buffer in temp_buffers {
+        temp_buffer.destroy();
+    }
+    Ok(pod_read_unaligned(&bytes?))
+}
+
+/// Check `buffer` can be bound as the input of a reduction, and holds at least one `T`.
+pub(crate) fn check_reducible<T: ReduceElement>(name: &str, buffer: &Buffer) -> Result<()> {
+    if !buffer.usage().contains(BufferUsages::STORAGE) {
+        return Err(Error::NotReducible(name.to_owned()));
+    }
+    if buffer.size() < std::mem::size_of::<T>() as u64 {
+        return Err(Error::NothingToReduce(name.to_owned()));
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+/// Generate `count` well distributed, non zero 32 bits seeds from `seed`, using splitmix64.
+pub(crate) fn splitmix64_seeds(count: u32, seed: u64) -> Vec<u32> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+
+            // xorshift-like generators get stuck on a zero state
+            match (z >> 32) as u32 {
+                0 => 1,
+                x => x,
+            }
+        })
+        .collect()
+}
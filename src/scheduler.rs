@@ -0,0 +1,95 @@
+use bevy::{
+    ecs::schedule::SystemSet,
+    log::error,
+    prelude::{Res, ResMut, Resource},
+    render::renderer::{RenderDevice, RenderQueue},
+    tasks::block_on,
+};
+use wgpu::{CommandBuffer, SubmissionIndex};
+
+/// The steps of the workers' frame in `PostUpdate`, in this order.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ComputeSet {
+    /// Workers record their run and hand it to the [`ComputeScheduler`].
+    Record,
+    /// The [`ComputeScheduler`] submits every recorded run at once.
+    Submit,
+    /// Workers map their results and check whether their run completed.
+    Finish,
+}
+
+/// Collects the runs recorded by every worker during a frame, and submits them together,
+/// so there is one queue submission per frame instead of one per worker.
+/// Immediate workers, `execute_now` and `execute_async` still submit on their own.
+#[derive(Resource, Default)]
+pub struct ComputeScheduler {
+    command_buffers: Vec<CommandBuffer>,
+    /// The number of workers whose run is in `command_buffers`.
+    workers: usize,
+    /// The submission of this frame's runs, and the GPU error it raised if it can be
+    /// attributed to a worker, that is if it was the only one in the batch.
+    pub(crate) last_batch: Option<(SubmissionIndex, Option<String>)>,
+    batch_error: Option<String>,
+}
+
+impl ComputeScheduler {
+    /// Push the run of a worker, to be submitted with the others.
+    pub(crate) fn push(&mut self, command_buffers: impl IntoIterator<Item = CommandBuffer>) {
+        self.command_buffers.extend(command_buffers);
+        self.workers += 1;
+    }
+
+    /// Take the last GPU error raised by submitting the runs of several workers at once.
+    /// Errors raised while recording are kept by each worker, see
+    /// [`AppComputeWorker::take_last_gpu_error`](crate::prelude::AppComputeWorker::take_last_gpu_error),
+    /// but submission errors, e.g. a buffer destroyed while still in use, fail the whole batch.
+    pub fn take_batch_error(&mut self) -> Option<String> {
+        self.batch_error.take()
+    }
+
+    pub(crate) fn submit(
+        mut scheduler: ResMut<Self>,
+        render_device: Res<RenderDevice>,
+        render_queue: Res<RenderQueue>,
+    ) {
+        if scheduler.command_buffers.is_empty() {
+            scheduler.last_batch = None;
+            return;
+        }
+
+        let command_buffers = std::mem::take(&mut scheduler.command_buffers);
+        let workers = std::mem::take(&mut scheduler.workers);
+        let (submission, mut gpu_error) =
+            submit_capturing_errors(&render_device, &render_queue, command_buffers);
+
+        // Giving it to every worker would blame the ones that did nothing wrong
+        if workers > 1 {
+            if let Some(err) = gpu_error.take() {
+                error!("Submitting the runs of {workers} workers failed: {err}");
+                scheduler.batch_error = Some(err);
+            }
+        }
+        scheduler.last_batch = Some((submission, gpu_error));
+    }
+}
+
+/// Submit `command_buffers`, returning their submission and the validation or out of memory
+/// error they raised, if any.
+pub(crate) fn submit_capturing_errors(
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+    command_buffers: impl IntoIterator<Item = CommandBuffer>,
+) -> (SubmissionIndex, Option<String>) {
+    let device = render_device.wgpu_device();
+
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let submission = render_queue.submit(command_buffers);
+    let validation_error = block_on(device.pop_error_scope());
+    let out_of_memory_error = block_on(device.pop_error_scope());
+
+    let error = validation_error
+        .or(out_of_memory_error)
+        .map(|err| err.to_string());
+    (submission, error)
+}
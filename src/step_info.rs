@@ -0,0 +1,63 @@
+use crate::worker::Step;
+
+/// A plain data view of a step of a worker, as returned by
+/// [`AppComputeWorker::steps`](crate::prelude::AppComputeWorker::steps).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StepInfo {
+    /// A compute pass.
+    Pass {
+        /// The type path of the shader.
+        shader: String,
+        /// The entry point run instead of the shader's own, see `add_pass_entry`.
+        entry_point: Option<String>,
+        /// The shader defs of this pass on top of the shader's own, see `add_pass_with_defs`.
+        shader_defs: Vec<String>,
+        label: Option<String>,
+        /// `None` when the size is only known at run time: computed by a closure,
+        /// or dispatched indirectly from a count or arguments computed on the GPU.
+        dispatch_size: Option<[u32; 3]>,
+        /// The fields bound to the pass, in binding order.
+        vars: Vec<String>,
+        /// The fields bound to bind groups 1 and up, see `add_pass_multi`.
+        extra_groups: Vec<Vec<String>>,
+        /// How many times the pass is dispatched, more than once for instanced passes.
+        instances: u32,
+        /// Whether the pass runs, see `set_pass_enabled`.
+        enabled: bool,
+    },
+    Swap(String, String),
+    Rotate(Vec<String>),
+    /// Zeroing a buffer, see `add_clear`.
+    Clear(String),
+    /// A closure recording its own commands, see `add_custom`.
+    Custom,
+}
+
+impl From<&Step> for StepInfo {
+    fn from(step: &Step) -> Self {
+        match step {
+            Step::ComputePass(compute_pass) => StepInfo::Pass {
+                shader: compute_pass.shader_type_path.clone(),
+                entry_point: compute_pass.entry_point.clone(),
+                shader_defs: compute_pass.shader_defs.clone(),
+                label: compute_pass.label.clone(),
+                dispatch_size: (compute_pass.dynamic_size.is_none()
+                    && compute_pass.indirect_count.is_none()
+                    && compute_pass.indirect_args.is_none())
+                .then_some(compute_pass.dispatch_size),
+                vars: compute_pass.vars.clone(),
+                extra_groups: compute_pass.extra_groups.clone(),
+                instances: compute_pass
+                    .instances
+                    .as_ref()
+                    .map_or(1, |instances| instances.count),
+                enabled: compute_pass.enabled,
+            },
+            Step::Swap(a, b) => StepInfo::Swap(a.clone(), b.clone()),
+            Step::Rotate(names) => StepInfo::Rotate(names.clone()),
+            Step::Clear(name) => StepInfo::Clear(name.clone()),
+            Step::Custom(_) => StepInfo::Custom,
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Helpers to exercise workers without a GPU, e.g. in headless CI.
+//!
+//! The app renders on wgpu's fallback adapter, a software implementation like
+//! Mesa's llvmpipe/lavapipe on Linux or WARP on Windows, which must be installed.
+//! Shaders really run, only slower, so readbacks hold actual results.
+
+use std::sync::Arc;
+
+use bevy::{
+    app::{App, PluginsState},
+    prelude::{default, DefaultPlugins, Mut, PluginGroup},
+    render::{
+        renderer::{initialize_renderer, RenderInstance},
+        settings::{RenderCreation, WgpuSettings},
+        RenderPlugin,
+    },
+    tasks::{block_on, tick_global_task_pools_on_main_thread},
+    window::{ExitCondition, WindowPlugin},
+    winit::WinitPlugin,
+};
+use wgpu::{Backends, InstanceDescriptor, RequestAdapterOptions};
+
+use crate::{
+    pipeline_cache::AppPipelineCache, plugin::AppComputePlugin, traits::ComputeWorker,
+    worker::AppComputeWorker,
+};
+
+/// A [`RenderPlugin`] creating the renderer on the fallback (software) adapter.
+/// Panics if the platform has none.
+pub fn software_render_plugin() -> RenderPlugin {
+    let instance = wgpu::Instance::new(InstanceDescriptor {
+        backends: Backends::all(),
+        ..default()
+    });
+    let (device, queue, adapter_info, adapter) = block_on(initialize_renderer(
+        &instance,
+        &WgpuSettings::default(),
+        &RequestAdapterOptions {
+            force_fallback_adapter: true,
+            ..default()
+        },
+    ));
+
+    RenderPlugin {
+        render_creation: RenderCreation::manual(
+            device,
+            queue,
+            adapter_info,
+            adapter,
+            RenderInstance(Arc::new(instance)),
+        ),
+        synchronous_pipeline_compilation: true,
+    }
+}
+
+/// A windowless app rendering on the software adapter, with the [`AppComputePlugin`].
+/// Add your [`AppComputeWorkerPlugin`](crate::prelude::AppComputeWorkerPlugin)s to it,
+/// then drive it with [`step_blocking`].
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            })
+            .set(software_render_plugin())
+            .disable::<WinitPlugin>(),
+        AppComputePlugin,
+    ));
+    app
+}
+
+/// Update `app` until the worker `W` has finished a run, at most `max_updates` times.
+/// Returns whether it finished. Pipelines take a few updates to be ready.
+pub fn step_blocking<W: ComputeWorker>(app: &mut App, max_updates: u32) -> bool {
+    finish_plugins(app);
+
+    for _ in 0..max_updates {
+        app.update();
+        if app.world.resource::<AppComputeWorker<W>>().ready() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Run the immediate worker `W` with `execute_now`, updating `app` until its pipelines
+/// are ready, at most `max_updates` times. Returns whether it ran.
+pub fn execute_now_blocking<W: ComputeWorker>(app: &mut App, max_updates: u32) -> bool {
+    finish_plugins(app);
+
+    for _ in 0..max_updates {
+        let executed = app
+            .world
+            .resource_scope(|world, mut worker: Mut<AppComputeWorker<W>>| {
+                worker.execute_now(world.resource::<AppPipelineCache>())
+            });
+        if executed {
+            return true;
+        }
+        app.update();
+    }
+    false
+}
+
+/// Finish the plugins of `app`, which builds the workers.
+/// `App::run` usually does it, but there's no runner here.
+pub fn finish_plugins(app: &mut App) {
+    if app.plugins_state() == PluginsState::Cleaned {
+        return;
+    }
+    while app.plugins_state() == PluginsState::Adding {
+        tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+}
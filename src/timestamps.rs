@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use bevy::{
+    log::warn,
+    render::{
+        render_resource::Buffer,
+        renderer::{RenderDevice, RenderQueue},
+    },
+};
+use bytemuck::pod_read_unaligned;
+use wgpu::{
+    BufferDescriptor, BufferUsages, CommandEncoder, ComputePassTimestampWrites, Features, QuerySet,
+    QuerySetDescriptor, QueryType, QUERY_SIZE,
+};
+
+use crate::{error::Result, once::read_buffer_blocking};
+
+/// GPU timestamps written at the beginning and the end of every compute pass of a worker.
+pub(crate) struct PassTimestamps {
+    query_set: Arc<QuerySet>,
+    /// The number of passes the query set has room for.
+    capacity: u32,
+    resolve_buffer: Buffer,
+    /// The label of every pass timed in the last recorded run, in order.
+    labels: Vec<String>,
+}
+
+impl PassTimestamps {
+    /// Returns `None` if the adapter can't write timestamps in compute passes.
+    pub(crate) fn new(render_device: &RenderDevice) -> Option<Self> {
+        if !render_device.features().contains(Features::TIMESTAMP_QUERY) {
+            warn!("The adapter doesn't support TIMESTAMP_QUERY, passes won't be timed");
+            return None;
+        }
+
+        let (query_set, resolve_buffer) = Self::create(render_device, 1);
+        Some(Self {
+            query_set,
+            capacity: 1,
+            resolve_buffer,
+            labels: Vec::new(),
+        })
+    }
+
+    fn create(render_device: &RenderDevice, capacity: u32) -> (Arc<QuerySet>, Buffer) {
+        let query_set = render_device
+            .wgpu_device()
+            .create_query_set(&QuerySetDescriptor {
+                label: Some("pass_timestamps"),
+                ty: QueryType::Timestamp,
+                count: 2 * capacity,
+            });
+        let resolve_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("pass_timestamps"),
+            size: 2 * capacity as u64 * QUERY_SIZE as u64,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        (Arc::new(query_set), resolve_buffer)
+    }
+
+    /// Start timing a new run of `passes` passes, growing the query set if needed.
+    pub(crate) fn begin_run(&mut self, render_device: &RenderDevice, passes: u32) {
+        if passes > self.capacity {
+            (self.query_set, self.resolve_buffer) = Self::create(render_device, passes);
+            self.capacity = passes;
+        }
+        self.labels.clear();
+    }
+
+    /// Register the next pass of the run, returning the index to give to [`Self::writes`].
+    pub(crate) fn push_pass(&mut self, label: &str) -> Option<u32> {
+        let index = self.labels.len() as u32;
+        if index >= self.capacity {
+            return None;
+        }
+        self.labels.push(label.to_owned());
+        Some(index)
+    }
+
+    pub(crate) fn writes(&self, index: u32) -> ComputePassTimestampWrites<'_> {
+        ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(2 * index),
+            end_of_pass_write_index: Some(2 * index + 1),
+        }
+    }
+
+    /// Copy the timestamps of the run to the resolve buffer, at the end of the run.
+    pub(crate) fn resolve(&self, encoder: &mut CommandEncoder) {
+        if self.labels.is_empty() {
+            return;
+        }
+        let count = 2 * self.labels.len() as u32;
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+    }
+
+    /// Wait for the last submitted run and return the duration of each of its passes,
+    /// in milliseconds.
+    pub(crate) fn read(
+        &self,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+    ) -> Result<Vec<(String, f64)>> {
+        if self.labels.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let bytes = read_buffer_blocking(render_device, render_queue, &self.resolve_buffer)?;
+        let ticks: Vec<u64> = bytes.chunks_exact(8).map(pod_read_unaligned).collect();
+        // Nanoseconds per tick
+        let period = render_queue.get_timestamp_period() as f64;
+
+        Ok(self
+            .labels
+            .iter()
+            .zip(ticks.chunks_exact(2))
+            .map(|(label, ticks)| {
+                let elapsed = ticks[1].wrapping_sub(ticks[0]) as f64 * period;
+                (label.clone(), elapsed / 1_000_000.)
+            })
+            .collect())
+    }
+}
@@ -1,15 +1,18 @@
-use crate::worker::AppComputeWorker;
+use crate::{
+    error::{Error, Result},
+    fields::ComputeFields,
+    worker::AppComputeWorker,
+};
 use bevy::{
     app::App,
     reflect::TypePath,
     render::render_resource::{BindGroupLayout, ShaderDefVal, ShaderRef},
 };
-use std::fmt::Debug;
 use wgpu::PushConstantRange;
 
 /// Trait to declare [`AppComputeWorker<W>`] structs.
 pub trait ComputeWorker: Sized + Send + Sync + 'static {
-    type Fields: Debug + Copy;
+    type Fields: ComputeFields;
     fn build(app: &mut App) -> AppComputeWorker<Self>;
 }
 
@@ -23,10 +26,14 @@ pub trait ComputeShader: TypePath + Send + Sync + 'static {
     ///     "shaders/my_shader.wgsl".into()
     /// }
     /// ```
+    ///
+    /// Shaders loaded from a path are hot reloaded with Bevy's `file_watcher` feature:
+    /// workers switch to the new pipeline once it compiles, and keep the previous one if it fails.
     fn shader() -> ShaderRef;
 
     /// If your shader has dependencies, declare them here.
-    /// The dependencies must be written in WGSL.
+    /// The dependencies are picked by extension: `.wgsl`, GLSL (`.glsl`, `.comp`, `.vert`, `.frag`),
+    /// or SPIR-V (`.spv`), the latter two needing Bevy's `shader_format_*` features.
     fn dependencies() -> Vec<ShaderRef> {
         vec![]
     }
@@ -49,4 +56,35 @@ pub trait ComputeShader: TypePath + Send + Sync + 'static {
     fn entry_point<'a>() -> &'a str {
         "main"
     }
+
+    /// The `@workgroup_size` of the entry point, used by the helpers dispatching
+    /// a pass for a number of elements, like `add_pass_for_elements`.
+    /// By default, it is `[64, 1, 1]`.
+    fn workgroup_size() -> [u32; 3] {
+        [64, 1, 1]
+    }
+}
+
+/// The number of workgroups of `S` needed to cover `elements` invocations along each axis.
+/// Fails if [`ComputeShader::workgroup_size`] has a zero, or differs from the `@workgroup_size`
+/// the shader `declared`, when it is known.
+pub(crate) fn workgroups_for<S: ComputeShader>(
+    elements: [u32; 3],
+    declared: Option<&[u32; 3]>,
+) -> Result<[u32; 3]> {
+    let workgroup_size = S::workgroup_size();
+    if workgroup_size.contains(&0) {
+        return Err(Error::InvalidWorkgroupSize(
+            S::type_path().to_string(),
+            workgroup_size,
+        ));
+    }
+    if let Some(declared) = declared.filter(|declared| **declared != workgroup_size) {
+        return Err(Error::WorkgroupSizeMismatch(
+            S::type_path().to_string(),
+            workgroup_size,
+            *declared,
+        ));
+    }
+    Ok([0, 1, 2].map(|axis| elements[axis].div_ceil(workgroup_size[axis])))
 }
@@ -1,50 +1,118 @@
-use core::panic;
-use std::{marker::PhantomData, ops::Deref};
+use std::{
+    any::Any,
+    fs,
+    future::Future,
+    marker::PhantomData,
+    num::NonZeroU64,
+    ops::{Deref, Range},
+    path::Path,
+    sync::{mpsc::Sender, Arc},
+};
 
 use crate::{
+    buffer_set::BufferSet,
+    completion::{Completion, CompletionFuture},
+    encoding::{encode_storage, encode_uniform},
     error::{Error, Result},
+    fields::{ComputeFields, ReadableField, WritableField},
+    indirect::IndirectCount,
+    once::{read_buffer_blocking, read_buffer_range_blocking, read_texture_blocking},
     pipeline_cache::AppPipelineCache,
-    traits::{ComputeShader, ComputeWorker},
+    reduce::{check_reducible, reduce_blocking, reduce_pipeline, ReduceElement, ReduceOp},
+    rng::splitmix64_seeds,
+    scheduler::{submit_capturing_errors, ComputeScheduler},
+    step_info::StepInfo,
+    timestamps::PassTimestamps,
+    traits::{workgroups_for, ComputeShader, ComputeWorker},
     worker_builder::AppComputeWorkerBuilder,
 };
 use bevy::{
-    prelude::{Res, ResMut, Resource},
+    log::{error, trace, warn},
+    prelude::{Event, EventWriter, Res, ResMut, Resource},
     render::{
         render_resource::{
-            encase::{internal::WriteInto, StorageBuffer, UniformBuffer},
-            Buffer, CachedComputePipelineId, ComputePipeline, ShaderType,
+            encase::{
+                internal::{CreateFrom, WriteInto},
+                StorageBuffer,
+            },
+            BindGroupLayout, Buffer, BufferId, CachedComputePipelineId, ComputePipeline,
+            ComputePipelineDescriptor, PipelineCache, ShaderDefVal, ShaderSize, ShaderType,
+            Texture, TextureView,
         },
         renderer::{RenderDevice, RenderQueue},
+        MainWorld,
     },
-    utils::HashMap,
+    tasks::block_on,
+    utils::{tracing, HashMap, HashSet},
 };
-use bytemuck::{bytes_of, cast_slice, from_bytes, AnyBitPattern, NoUninit};
+use bytemuck::{bytes_of, cast_slice, from_bytes, pod_read_unaligned, AnyBitPattern, NoUninit};
 
 use std::fmt::Debug;
 use wgpu::{
-    util::BufferInitDescriptor, BindGroupEntry, BufferDescriptor, BufferUsages, CommandEncoder,
-    CommandEncoderDescriptor, ComputePassDescriptor,
+    util::BufferInitDescriptor, BindGroupEntry, BindingResource, BufferBinding, BufferDescriptor,
+    BufferUsages, BufferView, CommandBuffer, CommandEncoder, CommandEncoderDescriptor,
+    ComputePassDescriptor, QuerySet, ShaderStages, SubmissionIndex, COPY_BUFFER_ALIGNMENT,
+    MAP_ALIGNMENT,
 };
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub enum RunMode {
     Continuous,
     OneShot(bool),
     Immediate,
+    /// Like `Continuous`, but only runs every `n` frames, `0` being treated as `1`.
+    EveryNFrames(u32),
 }
 
-#[derive(PartialEq)]
+/// The state of a worker between runs, see [`AppComputeWorker::state`].
+#[derive(PartialEq, Debug)]
 pub enum WorkerState {
+    /// Built, or reset, and yet to run.
     Created,
+    /// Done with the last run, whose results were read, and waiting to run again.
     Available,
+    /// A run is submitted and not done yet.
     Working,
+    /// The last run is done and its results can be read.
     FinishedWorking,
 }
 
+/// Sent once every time a run of the worker `W` completes and its results can be read,
+/// so systems can react with an `EventReader` instead of polling `ready()`.
+/// Immediate workers don't send it, `execute_now` returns their completion.
+#[derive(Event)]
+pub struct WorkerFinished<W: ComputeWorker> {
+    _phantom: PhantomData<W>,
+}
+
+impl<W: ComputeWorker> Default for WorkerFinished<W> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Coarse progress of a worker run, see [`AppComputeWorker::current_phase`].
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Phase {
+    /// The next run is yet to be recorded and submitted, e.g. while the pipelines compile.
+    Recording,
+    /// The run is submitted and the GPU is working on it.
+    Submitted,
+    /// The GPU is done, the staging buffers are being mapped.
+    Mapping,
+    /// The results of the run can be read.
+    Ready,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum Step {
-    ComputePass(ComputePass),
+    ComputePass(Box<ComputePass>),
     Swap(String, String),
+    Rotate(Vec<String>),
+    Clear(String),
+    Custom(CustomStep),
 }
 
 #[derive(Clone, Debug)]
@@ -52,12 +120,267 @@ pub(crate) struct ComputePass {
     pub(crate) dispatch_size: [u32; 3],
     pub(crate) vars: Vec<String>,
     pub(crate) shader_type_path: String,
+    /// Identifies the pipeline variant of the pass, which keys its pipeline and bind group layout.
+    pub(crate) pipeline_key: String,
+    /// The entry point run instead of the shader's own, see `add_pass_entry`.
+    pub(crate) entry_point: Option<String>,
+    /// The shader defs of the pass on top of the shader's own, as text.
+    pub(crate) shader_defs: Vec<String>,
+    pub(crate) label: Option<String>,
+    pub(crate) instances: Option<Instances>,
+    pub(crate) indirect_count: Option<IndirectCount>,
+    /// The buffer, and offset in it, of the indirect dispatch arguments of the pass.
+    pub(crate) indirect_args: Option<(String, u64)>,
+    /// The fields bound to bind groups 1 and up, `vars` being bound to group 0.
+    pub(crate) extra_groups: Vec<Vec<String>>,
+    pub(crate) dynamic_size: Option<DynamicSize>,
+    /// Disabled passes are skipped when recording, see `set_pass_enabled`.
+    pub(crate) enabled: bool,
+}
+
+impl ComputePass {
+    /// An enabled pass of `shader_type_path` binding `vars` to group 0,
+    /// with an empty dispatch size and no entry point, defs, label or indirection.
+    pub(crate) fn new(shader_type_path: String, pipeline_key: String, vars: Vec<String>) -> Self {
+        Self {
+            dispatch_size: [0, 0, 0],
+            vars,
+            shader_type_path,
+            pipeline_key,
+            entry_point: None,
+            shader_defs: Vec::new(),
+            label: None,
+            instances: None,
+            indirect_count: None,
+            indirect_args: None,
+            extra_groups: Vec::new(),
+            dynamic_size: None,
+            enabled: true,
+        }
+    }
+}
+
+/// The key of the pipeline of `shader` run from `entry_point` instead of its own, if given,
+/// and compiled with the extra `shader_defs`.
+pub(crate) fn pipeline_key(
+    shader: &str,
+    entry_point: Option<&str>,
+    shader_defs: &[String],
+) -> String {
+    let shader = match entry_point {
+        Some(entry_point) => format!("{shader}#{entry_point}"),
+        None => shader.to_owned(),
+    };
+    if shader_defs.is_empty() {
+        shader
+    } else {
+        format!("{shader} {shader_defs:?}")
+    }
+}
+
+/// The text form of `shader_defs`, as stored in passes and [`StepInfo`].
+pub(crate) fn shader_defs_text(shader_defs: &[ShaderDefVal]) -> Vec<String> {
+    shader_defs.iter().map(|def| format!("{def:?}")).collect()
+}
+
+/// Check no dimension of `dispatch_size` exceeds the workgroups the device can dispatch.
+pub(crate) fn check_dispatch_size(
+    render_device: &RenderDevice,
+    dispatch_size: [u32; 3],
+) -> Result<()> {
+    let max = render_device.limits().max_compute_workgroups_per_dimension;
+    match dispatch_size.iter().position(|&size| size > max) {
+        Some(dim) => Err(Error::DispatchTooLarge {
+            dim,
+            requested: dispatch_size[dim],
+            max,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Check the buffers behind `names` can be swapped or rotated: there is at least one,
+/// each is listed once, and they all have the same size and usages.
+pub(crate) fn check_swappable(buffers: &HashMap<String, Buffer>, names: &[&String]) -> Result<()> {
+    let Some((first_name, others)) = names.split_first() else {
+        return Err(Error::InvalidStep(format!("{:?}", Step::Rotate(vec![]))));
+    };
+
+    let mut seen = HashSet::default();
+    for name in names {
+        if !seen.insert(name) {
+            return Err(Error::DuplicateField(name.to_string()));
+        }
+        if !buffers.contains_key(*name) {
+            return Err(Error::BufferNotFound(name.to_string()));
+        }
+    }
+
+    let first = &buffers[*first_name];
+    for name in others {
+        let buffer = &buffers[*name];
+        // Directly mapped staging buffers are swapped through a regular staging buffer
+        let usage = |buffer: &Buffer| buffer.usage().difference(BufferUsages::MAP_READ);
+        if buffer.size() != first.size() || usage(buffer) != usage(first) {
+            return Err(Error::IncompatibleSwap(
+                first_name.to_string(),
+                name.to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Replace `staging`, if it is the buffer of `name` itself, by a regular staging buffer
+/// holding the same data, as swapped buffers move between fields.
+fn detach_primary_staging(render_device: &RenderDevice, name: &str, staging: &mut StagingBuffer) {
+    if !staging.primary {
+        return;
+    }
+
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some(name),
+        size: staging.buffer.size(),
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: staging.mapped,
+    });
+    if staging.mapped {
+        buffer
+            .slice(..)
+            .get_mapped_range_mut()
+            .copy_from_slice(&staging.buffer.slice(..).get_mapped_range());
+        staging.buffer.unmap();
+    }
+    *staging = StagingBuffer {
+        mapped: staging.mapped,
+        buffer,
+        primary: false,
+    };
+}
+
+/// Check `bytes` written `offset` bytes in are aligned as the queue needs.
+fn check_write_alignment(name: &str, offset: u64, bytes: &[u8]) -> Result<()> {
+    let len = bytes.len() as u64;
+    if !offset.is_multiple_of(COPY_BUFFER_ALIGNMENT) || !len.is_multiple_of(COPY_BUFFER_ALIGNMENT) {
+        return Err(Error::MisalignedWrite(name.to_owned(), offset, len));
+    }
+    Ok(())
+}
+
+/// Check `bytes` are aligned and fit in `buffer` when written `offset` bytes in.
+fn check_write_bounds(name: &str, buffer: &Buffer, offset: u64, bytes: &[u8]) -> Result<()> {
+    check_write_alignment(name, offset, bytes)?;
+    let len = bytes.len() as u64;
+    match offset.checked_add(len) {
+        Some(end) if end <= buffer.size() => Ok(()),
+        _ => Err(Error::OutOfBounds(name.to_owned(), offset, len)),
+    }
+}
+
+type SizeFn = dyn Fn(&dyn Any) -> [u32; 3] + Send + Sync;
+
+/// Computes the dispatch size of a pass from its worker, right before the pass is dispatched.
+#[derive(Clone)]
+pub(crate) struct DynamicSize(Arc<SizeFn>);
+
+impl DynamicSize {
+    pub(crate) fn new<W: ComputeWorker>(
+        size_fn: impl Fn(&AppComputeWorker<W>) -> [u32; 3] + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(move |worker| {
+            size_fn(
+                worker
+                    .downcast_ref()
+                    .expect("Dynamic size evaluated with another worker"),
+            )
+        }))
+    }
+
+    fn evaluate<W: ComputeWorker>(&self, worker: &AppComputeWorker<W>) -> [u32; 3] {
+        (self.0)(worker)
+    }
+}
+
+impl Debug for DynamicSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DynamicSize")
+    }
+}
+
+type CustomFn = dyn Fn(&mut CommandEncoder, &HashMap<String, Buffer>) + Send + Sync;
+
+/// Records arbitrary commands between the other steps, with the live encoder and buffers.
+#[derive(Clone)]
+pub(crate) struct CustomStep(Arc<CustomFn>);
+
+impl CustomStep {
+    pub(crate) fn new(
+        f: impl Fn(&mut CommandEncoder, &HashMap<String, Buffer>) + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+impl Debug for CustomStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CustomStep")
+    }
+}
+
+/// What a pipeline was queued with, to queue it again with other shader defs.
+#[derive(Clone, Debug)]
+pub(crate) struct PipelineSource {
+    pub(crate) descriptor: ComputePipelineDescriptor,
+    /// The shader defs of the passes on top of the shader's own, see `add_pass_with_defs`.
+    pub(crate) pass_defs: Vec<ShaderDefVal>,
+}
+
+/// A pass dispatched `count` times, binding the next `stride` bytes of `param` each time.
+#[derive(Clone, Debug)]
+pub(crate) struct Instances {
+    pub(crate) param: String,
+    pub(crate) count: u32,
+    pub(crate) stride: u32,
+}
+
+type FieldCallback = Box<dyn FnMut(&[u8]) + Send + Sync>;
+type CompleteCallback<W> = Box<dyn FnMut(&AppComputeWorker<W>) + Send + Sync>;
+
+/// Raw bytes read from a buffer, either straight from a mapped staging buffer
+/// or copied out of a buffer without one.
+enum RawData<'a> {
+    Mapped(BufferView<'a>),
+    /// Part of a mapped range, which is wider to respect the mapping alignment.
+    MappedPart(BufferView<'a>, Range<usize>),
+    Copied(Vec<u8>),
+}
+
+impl Deref for RawData<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            RawData::Mapped(view) => view,
+            RawData::MappedPart(view, range) => &view[range.clone()],
+            RawData::Copied(bytes) => bytes,
+        }
+    }
+}
+
+/// A texture bound to passes through a view of the whole texture.
+#[derive(Clone, Debug)]
+pub(crate) struct BoundTexture {
+    pub(crate) texture: Texture,
+    pub(crate) view: TextureView,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct StagingBuffer {
     pub(crate) mapped: bool,
     pub(crate) buffer: Buffer,
+    /// The buffer is the storage buffer itself, mapped directly for reading.
+    /// Only possible with `MAPPABLE_PRIMARY_BUFFERS`.
+    pub(crate) primary: bool,
 }
 
 /// Struct to manage data transfers from/to the GPU
@@ -71,17 +394,99 @@ pub struct AppComputeWorker<W: ComputeWorker> {
     render_device: RenderDevice,
     render_queue: RenderQueue,
     cached_pipeline_ids: HashMap<String, CachedComputePipelineId>,
+    /// The pipeline queued for each pipeline key and shader defs, reused by `set_shader_defs`
+    /// instead of compiling the same variant again.
+    pipeline_ids_by_defs: HashMap<(String, Vec<String>), CachedComputePipelineId>,
     pipelines: HashMap<String, Option<ComputePipeline>>,
+    bind_group_layouts: HashMap<String, BindGroupLayout>,
+    /// Pipelines whose bind group layout was reflected from the shader, refreshed on reload.
+    reflected_layouts: HashSet<String>,
+    /// The compilation error of each pipeline that failed, until it compiles.
+    pipeline_errors: HashMap<String, String>,
     buffers: HashMap<String, Buffer>,
+    textures: HashMap<String, BoundTexture>,
+    initial_contents: HashMap<String, Vec<u8>>,
+    /// The buffers supplied by the user, which `reset` leaves untouched.
+    existing_buffers: HashSet<BufferId>,
+    binding_sizes: HashMap<String, u64>,
     staging_buffers: HashMap<String, StagingBuffer>,
+    query_sets: HashMap<String, (Arc<QuerySet>, u32)>,
+    timestamps: Option<PassTimestamps>,
     steps: Vec<Step>,
     command_encoder: Option<CommandEncoder>,
+    /// What was recorded before the run, like the copies of `resize_buffer`, submitted first.
+    /// Kept apart so a failed run doesn't drop it.
+    pre_run_commands: Option<CommandBuffer>,
+    /// The buffers as they were before the run being recorded, put back if it fails.
+    /// Moved forward whenever a chunk of the run is submitted, see `submit_chunk`.
+    buffers_before_run: HashMap<String, Buffer>,
     run_mode: RunMode,
     wait_mode: bool,
+    pub(crate) manual_unmap: bool,
+    submit_every: Option<u32>,
+    last_gpu_error: Option<String>,
+    last_error: Option<Error>,
+    completion: Arc<Completion>,
+    field_callbacks: HashMap<String, FieldCallback>,
+    complete_callbacks: Vec<CompleteCallback<W>>,
+    completion_senders: Vec<Sender<()>>,
+    pending_writes: Vec<(String, u64, Vec<u8>)>,
+    /// The pipelines of `reduce`, created on first use for each element type and operation.
+    reduce_pipelines: HashMap<String, ComputePipeline>,
+    /// Push constants set with `set_push_constants`, keyed by shader type path.
+    push_constants: HashMap<String, Vec<u8>>,
+    last_submission: Option<SubmissionIndex>,
+    frames_in_flight: u32,
+    /// Frames the worker was updated, to only run on some with `RunMode::EveryNFrames`.
+    frame: u32,
+    max_frames_in_flight: Option<u32>,
+    /// Indices of the passes already warned about dispatching no workgroup.
+    empty_dispatch_warned: HashSet<usize>,
+    pipeline_sources: HashMap<String, PipelineSource>,
+    /// The `@workgroup_size` of each pipeline, when its shader can be parsed on its own.
+    workgroup_sizes: HashMap<String, [u32; 3]>,
+    /// Pipelines to queue again in the render world, see `set_shader_defs`.
+    pending_pipelines: Vec<(String, ComputePipelineDescriptor)>,
+    /// The run recorded this frame waits for the [`ComputeScheduler`] to submit it.
+    batched: bool,
     _phantom: PhantomData<W>,
 }
 
-impl<W: ComputeWorker, E: Debug + Copy> From<&AppComputeWorkerBuilder<'_, W, E>>
+impl<W: ComputeWorker> Debug for AppComputeWorker<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut buffers = self.buffers.keys().collect::<Vec<_>>();
+        buffers.sort();
+
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::ComputePass(compute_pass) => format!(
+                    "Pass({})",
+                    compute_pass
+                        .label
+                        .as_deref()
+                        .unwrap_or(&compute_pass.shader_type_path)
+                ),
+                Step::Swap(a, b) => format!("Swap({a}, {b})"),
+                Step::Rotate(names) => format!("Rotate({})", names.join(", ")),
+                Step::Clear(name) => format!("Clear({name})"),
+                Step::Custom(_) => "Custom".to_owned(),
+            })
+            .collect::<Vec<_>>();
+
+        f.debug_struct("AppComputeWorker")
+            .field("worker", &std::any::type_name::<W>())
+            .field("state", &self.state)
+            .field("run_mode", &self.run_mode)
+            .field("wait_mode", &self.wait_mode)
+            .field("buffers", &buffers)
+            .field("steps", &steps)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: ComputeWorker, E: ComputeFields> From<&AppComputeWorkerBuilder<'_, W, E>>
     for AppComputeWorker<W>
 {
     /// Create a new [`AppComputeWorker<W>`].
@@ -95,21 +500,102 @@ impl<W: ComputeWorker, E: Debug + Copy> From<&AppComputeWorkerBuilder<'_, W, E>>
             .map(|(type_path, _)| (type_path.clone(), None))
             .collect();
 
+        let pipeline_ids_by_defs = builder
+            .pipeline_sources
+            .iter()
+            .filter_map(|(pipeline_key, source)| {
+                let cached_id = builder.cached_pipeline_ids.get(pipeline_key)?;
+                let defs = shader_defs_text(&source.descriptor.shader_defs);
+                Some(((pipeline_key.clone(), defs), *cached_id))
+            })
+            .collect();
+
         let command_encoder =
             Some(render_device.create_command_encoder(&CommandEncoderDescriptor { label: None }));
 
+        // Submitted right away, as the encoder of the first run is replaced until it is recorded
+        if !builder.zeroed.is_empty() {
+            let mut encoder =
+                render_device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+            for (name, id) in &builder.zeroed {
+                match builder.buffers.get(name) {
+                    Some(buffer) if buffer.id() == *id => encoder.clear_buffer(buffer, 0, None),
+                    _ => {}
+                }
+            }
+            render_queue.submit(Some(encoder.finish()));
+        }
+
+        let state = if builder.initially_ready {
+            WorkerState::FinishedWorking
+        } else {
+            WorkerState::Created
+        };
+
+        // Swapped buffers move between fields, so their staging buffer can't be the buffer itself
+        let mut staging_buffers = builder.staging_buffers.clone();
+        for step in &builder.steps {
+            let names = match step {
+                Step::Swap(a, b) => vec![a, b],
+                Step::Rotate(names) => names.iter().collect(),
+                Step::ComputePass(_) | Step::Clear(_) | Step::Custom(_) => continue,
+            };
+            for name in names {
+                if let Some(staging) = staging_buffers.get_mut(name) {
+                    detach_primary_staging(&render_device, name, staging);
+                }
+            }
+        }
+
+        let timestamps = builder
+            .timestamps
+            .then(|| PassTimestamps::new(&render_device))
+            .flatten();
+
         Self {
-            state: WorkerState::Created,
+            state,
             render_device,
             render_queue,
             cached_pipeline_ids: builder.cached_pipeline_ids.clone(),
+            pipeline_ids_by_defs,
             pipelines,
+            bind_group_layouts: builder.bind_group_layouts.clone(),
+            reflected_layouts: HashSet::default(),
+            pipeline_errors: HashMap::default(),
             buffers: builder.buffers.clone(),
-            staging_buffers: builder.staging_buffers.clone(),
+            textures: builder.textures.clone(),
+            initial_contents: builder.initial_contents.clone(),
+            existing_buffers: builder.existing_buffers.clone(),
+            binding_sizes: builder.binding_sizes.clone(),
+            staging_buffers,
+            query_sets: builder.query_sets.clone(),
+            timestamps,
             steps: builder.steps.clone(),
             command_encoder,
+            pre_run_commands: None,
+            buffers_before_run: HashMap::default(),
             run_mode: builder.run_mode,
             wait_mode: builder.wait_mode,
+            manual_unmap: builder.manual_unmap,
+            submit_every: builder.submit_every,
+            last_gpu_error: None,
+            last_error: None,
+            completion: Completion::new(0, Vec::new()),
+            field_callbacks: HashMap::default(),
+            complete_callbacks: Vec::new(),
+            completion_senders: Vec::new(),
+            pending_writes: Vec::new(),
+            reduce_pipelines: HashMap::default(),
+            push_constants: HashMap::default(),
+            last_submission: None,
+            frames_in_flight: 0,
+            frame: 0,
+            max_frames_in_flight: None,
+            empty_dispatch_warned: HashSet::default(),
+            pipeline_sources: builder.pipeline_sources.clone(),
+            workgroup_sizes: builder.workgroup_sizes.clone(),
+            pending_pipelines: Vec::new(),
+            batched: false,
             _phantom: PhantomData,
         }
     }
@@ -119,98 +605,467 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
     pub fn run_mode(&self) -> RunMode {
         self.run_mode
     }
+
+    /// The size in bytes of the buffer behind `target`, if it exists.
+    pub fn buffer_size(&self, target: W::Fields) -> Option<u64> {
+        self.buffers.get(target.key()).map(|buffer| buffer.size())
+    }
+
+    /// The GPU buffer behind `target`, if it exists, to bind it in your own render pipelines
+    /// without reading it back. It derefs to the raw [`wgpu::Buffer`].
+    /// Swap steps exchange buffers between fields, and rebuilding or resizing replaces them,
+    /// so fetch it again every frame instead of keeping it around.
+    pub fn gpu_buffer(&self, target: W::Fields) -> Option<&Buffer> {
+        self.buffers.get(target.key())
+    }
+
+    /// Which of the swapped pair `written` and `other` holds the data written by the last run,
+    /// `written` being the field the passes write to.
+    ///
+    /// The swap steps after the last pass binding either field move that data around:
+    /// after an odd number of swaps it sits in `other`, after an even number in `written`.
+    /// Read the returned field instead of hardcoding one side of a ping-pong pair.
+    /// `None` if rotate steps moved it to a third field.
+    pub fn latest_output(&self, written: W::Fields, other: W::Fields) -> Option<W::Fields> {
+        let (written_key, other_key) = (written.key(), other.key());
+        let last_pass = self.steps.iter().rposition(|step| match step {
+            Step::ComputePass(pass) => {
+                pass.enabled
+                    && pass
+                        .vars
+                        .iter()
+                        .chain(pass.extra_groups.iter().flatten())
+                        .any(|var| var == written_key || var == other_key)
+            }
+            _ => false,
+        });
+        let Some(last_pass) = last_pass else {
+            return Some(written);
+        };
+
+        let mut holder: &str = written_key;
+        for step in &self.steps[last_pass + 1..] {
+            match step {
+                Step::Swap(a, b) if a == holder => holder = b,
+                Step::Swap(a, b) if b == holder => holder = a,
+                Step::Rotate(names) => {
+                    if let Some(i) = names.iter().position(|name| name == holder) {
+                        holder = &names[(i + 1) % names.len()];
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if holder == written_key {
+            Some(written)
+        } else if holder == other_key {
+            Some(other)
+        } else {
+            None
+        }
+    }
+
+    /// The most workgroups the device can dispatch in each dimension,
+    /// to clamp dispatch sizes before setting them.
+    pub fn max_dispatch_size(&self) -> [u32; 3] {
+        let max = self
+            .render_device
+            .limits()
+            .max_compute_workgroups_per_dimension;
+        [max; 3]
+    }
+
+    /// The raw [`wgpu::Device`] this worker runs on.
+    /// Useful to create resources the worker doesn't manage, like query sets or samplers.
+    pub fn wgpu_device(&self) -> &wgpu::Device {
+        self.render_device.wgpu_device()
+    }
+
+    /// The raw [`wgpu::Queue`] this worker submits its work to.
+    pub fn wgpu_queue(&self) -> &wgpu::Queue {
+        &self.render_queue
+    }
+
+    /// Bind only the first `size` bytes of `target` instead of the whole buffer,
+    /// or the whole buffer again with `None`.
+    pub fn try_set_binding_size(&mut self, target: W::Fields, size: Option<u64>) -> Result<()> {
+        let name = target.key().to_owned();
+        let Some(buffer) = self.buffers.get(&name) else {
+            return Err(Error::BufferNotFound(name));
+        };
+
+        match size {
+            Some(size) if size == 0 || size > buffer.size() => {
+                return Err(Error::InvalidBindingSize(name, size))
+            }
+            Some(size) => self.binding_sizes.insert(name, size),
+            None => self.binding_sizes.remove(&name),
+        };
+        Ok(())
+    }
+
+    /// Bind only the first `size` bytes of `target` instead of the whole buffer,
+    /// or the whole buffer again with `None`.
+    /// In case of error, this function will panic.
+    pub fn set_binding_size(&mut self, target: W::Fields, size: Option<u64>) {
+        self.try_set_binding_size(target, size).unwrap()
+    }
+
+    /// Set the dispatch size of the pass running `S` from its own entry point, without extra defs.
+    /// This replaces the closure of a pass added with a dynamic size.
+    /// Fails if no such pass runs `S`, or if `dispatch_size` exceeds
+    /// [`Self::max_dispatch_size`].
+    pub fn try_set_dispatch_size<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+    ) -> Result<()> {
+        self.set_pass_dispatch_size(pipeline_key(S::type_path(), None, &[]), dispatch_size)
+    }
+
+    /// Set the dispatch size of the pass running `S` from its own entry point, without extra defs.
+    /// In case of error, this function will panic.
     pub fn set_dispatch_size<S: ComputeShader>(&mut self, dispatch_size: [u32; 3]) {
-        let shader_index = self
+        self.try_set_dispatch_size::<S>(dispatch_size).unwrap()
+    }
+
+    /// Set the dispatch size of the pass running `S` compiled with the extra `shader_defs`,
+    /// added with `add_pass_with_defs` or another `_with_defs` variant.
+    /// Fails if no pass runs `S` with these defs, or like `try_set_dispatch_size`.
+    pub fn try_set_dispatch_size_with_defs<S: ComputeShader>(
+        &mut self,
+        shader_defs: &[ShaderDefVal],
+        dispatch_size: [u32; 3],
+    ) -> Result<()> {
+        let pipeline_key = pipeline_key(S::type_path(), None, &shader_defs_text(shader_defs));
+        self.set_pass_dispatch_size(pipeline_key, dispatch_size)
+    }
+
+    /// Set the dispatch size of the pass running `S` compiled with the extra `shader_defs`.
+    /// In case of error, this function will panic.
+    pub fn set_dispatch_size_with_defs<S: ComputeShader>(
+        &mut self,
+        shader_defs: &[ShaderDefVal],
+        dispatch_size: [u32; 3],
+    ) {
+        self.try_set_dispatch_size_with_defs::<S>(shader_defs, dispatch_size)
+            .unwrap()
+    }
+
+    /// Set the dispatch size of the pass running the `entry` function of `S`,
+    /// added with `add_pass_entry`.
+    /// Fails if no pass runs `S` from `entry`.
+    pub fn try_set_entry_dispatch_size<S: ComputeShader>(
+        &mut self,
+        entry: &str,
+        dispatch_size: [u32; 3],
+    ) -> Result<()> {
+        let entry_point = (entry != S::entry_point()).then_some(entry);
+        let pipeline_key = pipeline_key(S::type_path(), entry_point, &[]);
+        self.set_pass_dispatch_size(pipeline_key, dispatch_size)
+    }
+
+    /// Set the dispatch size of the pass running the `entry` function of `S`.
+    /// In case of error, this function will panic.
+    pub fn set_entry_dispatch_size<S: ComputeShader>(
+        &mut self,
+        entry: &str,
+        dispatch_size: [u32; 3],
+    ) {
+        self.try_set_entry_dispatch_size::<S>(entry, dispatch_size)
+            .unwrap()
+    }
+
+    fn set_pass_dispatch_size(
+        &mut self,
+        pipeline_key: String,
+        dispatch_size: [u32; 3],
+    ) -> Result<()> {
+        check_dispatch_size(&self.render_device, dispatch_size)?;
+
+        let compute_pass = self.steps.iter_mut().find_map(|step| match step {
+            Step::ComputePass(compute_pass) if compute_pass.pipeline_key == pipeline_key => {
+                Some(compute_pass)
+            }
+            _ => None,
+        });
+        let Some(compute_pass) = compute_pass else {
+            return Err(Error::ShaderNotFound(pipeline_key));
+        };
+
+        compute_pass.dispatch_size = dispatch_size;
+        compute_pass.dynamic_size = None;
+        Ok(())
+    }
+
+    /// Skip every pass running `S` from its own entry point without extra defs when the worker
+    /// runs, or run them again.
+    /// The passes stay in place, so re-enabling them doesn't rebuild anything.
+    /// Swap and rotate steps around them still run unless they are removed too.
+    /// Fails if no such pass runs `S`.
+    pub fn try_set_pass_enabled<S: ComputeShader>(&mut self, enabled: bool) -> Result<()> {
+        self.set_passes_enabled(pipeline_key(S::type_path(), None, &[]), enabled)
+    }
+
+    /// Skip every pass running `S` without extra defs when the worker runs, or run them again.
+    /// In case of error, this function will panic.
+    pub fn set_pass_enabled<S: ComputeShader>(&mut self, enabled: bool) {
+        self.try_set_pass_enabled::<S>(enabled).unwrap()
+    }
+
+    /// Skip every pass running `S` compiled with the extra `shader_defs` when the worker runs,
+    /// or run them again. Fails if no pass runs `S` with these defs.
+    pub fn try_set_pass_enabled_with_defs<S: ComputeShader>(
+        &mut self,
+        shader_defs: &[ShaderDefVal],
+        enabled: bool,
+    ) -> Result<()> {
+        let pipeline_key = pipeline_key(S::type_path(), None, &shader_defs_text(shader_defs));
+        self.set_passes_enabled(pipeline_key, enabled)
+    }
+
+    /// Skip every pass running `S` compiled with the extra `shader_defs`, or run them again.
+    /// In case of error, this function will panic.
+    pub fn set_pass_enabled_with_defs<S: ComputeShader>(
+        &mut self,
+        shader_defs: &[ShaderDefVal],
+        enabled: bool,
+    ) {
+        self.try_set_pass_enabled_with_defs::<S>(shader_defs, enabled)
+            .unwrap()
+    }
+
+    fn set_passes_enabled(&mut self, pipeline_key: String, enabled: bool) -> Result<()> {
+        let mut found = false;
+        for step in &mut self.steps {
+            if let Step::ComputePass(compute_pass) = step {
+                if compute_pass.pipeline_key == pipeline_key {
+                    compute_pass.enabled = enabled;
+                    found = true;
+                }
+            }
+        }
+
+        if !found {
+            return Err(Error::ShaderNotFound(pipeline_key));
+        }
+        Ok(())
+    }
+
+    /// Set the dispatch size of the pass running `S` without extra defs to run one invocation
+    /// per element, according to [`ComputeShader::workgroup_size`].
+    /// Fails if the workgroup size has a zero, or doesn't match the `@workgroup_size` of the shader,
+    /// or like `try_set_dispatch_size`.
+    pub fn try_dispatch_for<S: ComputeShader>(&mut self, elements: [u32; 3]) -> Result<()> {
+        self.try_dispatch_for_with_defs::<S>(&[], elements)
+    }
+
+    /// Set the dispatch size of the pass running `S` without extra defs to run one invocation
+    /// per element.
+    /// In case of error, this function will panic.
+    pub fn dispatch_for<S: ComputeShader>(&mut self, elements: [u32; 3]) {
+        self.try_dispatch_for::<S>(elements).unwrap()
+    }
+
+    /// Set the dispatch size of the pass running `S` compiled with the extra `shader_defs`
+    /// to run one invocation per element, checked against its `@workgroup_size`
+    /// with the defs applied.
+    /// Fails like `try_dispatch_for`.
+    pub fn try_dispatch_for_with_defs<S: ComputeShader>(
+        &mut self,
+        shader_defs: &[ShaderDefVal],
+        elements: [u32; 3],
+    ) -> Result<()> {
+        let pipeline_key = pipeline_key(S::type_path(), None, &shader_defs_text(shader_defs));
+        let declared = self.workgroup_sizes.get(&pipeline_key);
+        let dispatch_size = workgroups_for::<S>(elements, declared)?;
+        self.set_pass_dispatch_size(pipeline_key, dispatch_size)
+    }
+
+    /// Set the dispatch size of the pass running `S` compiled with the extra `shader_defs`
+    /// to run one invocation per element.
+    /// In case of error, this function will panic.
+    pub fn dispatch_for_with_defs<S: ComputeShader>(
+        &mut self,
+        shader_defs: &[ShaderDefVal],
+        elements: [u32; 3],
+    ) {
+        self.try_dispatch_for_with_defs::<S>(shader_defs, elements)
+            .unwrap()
+    }
+
+    /// Set the push constants of every pass running `S`, from offset 0, e.g. a delta time.
+    /// They're kept for the next runs until set again.
+    /// Fails if no pass runs `S`, or if `data` isn't a multiple of 4 bytes covered by one of
+    /// the compute ranges of [`ComputeShader::push_constant_ranges`].
+    pub fn try_set_push_constants<S: ComputeShader>(&mut self, data: &[u8]) -> Result<()> {
+        let runs_shader = self.steps.iter().any(|step| match step {
+            Step::ComputePass(compute_pass) => compute_pass.shader_type_path == S::type_path(),
+            _ => false,
+        });
+        if !runs_shader {
+            return Err(Error::ShaderNotFound(S::type_path().to_string()));
+        }
+
+        let len = data.len() as u32;
+        let in_range = S::push_constant_ranges().iter().any(|range| {
+            range.stages.contains(ShaderStages::COMPUTE)
+                && range.range.start == 0
+                && len <= range.range.end
+        });
+        if !in_range || len == 0 || !len.is_multiple_of(4) {
+            return Err(Error::InvalidPushConstants(
+                S::type_path().to_string(),
+                data.len(),
+            ));
+        }
+
+        self.push_constants
+            .insert(S::type_path().to_string(), data.to_vec());
+        Ok(())
+    }
+
+    /// Set the push constants of every pass running `S`, from offset 0.
+    /// In case of error, this function will panic.
+    pub fn set_push_constants<S: ComputeShader>(&mut self, data: &[u8]) {
+        self.try_set_push_constants::<S>(data).unwrap()
+    }
+
+    /// Compile the passes running `S` with `defs` in place of [`ComputeShader::shader_defs`],
+    /// keeping the defs of passes added with `add_pass_with_defs` on top, e.g. to toggle a feature.
+    /// The pipelines are queued again at the end of the frame and compile asynchronously:
+    /// until they are ready the worker doesn't run, and `ready()` is false unless a run
+    /// was already submitted, which completes with the previous defs.
+    /// Switching back to defs used before reuses their pipeline instead of compiling it again.
+    /// Fails if no pass runs `S`.
+    pub fn try_set_shader_defs<S: ComputeShader>(&mut self, defs: Vec<ShaderDefVal>) -> Result<()> {
+        let pipeline_keys = self
             .steps
             .iter()
-            .position(|step| match step {
-                Step::ComputePass(compute_pass) => {
-                    compute_pass.shader_type_path == S::type_path().to_string()
+            .filter_map(|step| match step {
+                Step::ComputePass(compute_pass)
+                    if compute_pass.shader_type_path == S::type_path() =>
+                {
+                    Some(compute_pass.pipeline_key.clone())
                 }
-                Step::Swap(_, _) => false,
+                _ => None,
             })
-            .expect(&format!("Shader {} not found", S::type_path()));
+            .collect::<HashSet<_>>();
+        if pipeline_keys.is_empty() {
+            return Err(Error::ShaderNotFound(S::type_path().to_string()));
+        }
 
-        match &self.steps[shader_index] {
-            Step::ComputePass(compute_pass) => {
-                let mut new_compute_pass = compute_pass.clone();
-                new_compute_pass.dispatch_size = dispatch_size;
-                self.steps[shader_index] = Step::ComputePass(new_compute_pass);
+        for pipeline_key in pipeline_keys {
+            let Some(source) = self.pipeline_sources.get(&pipeline_key) else {
+                return Err(Error::ShaderNotFound(pipeline_key));
+            };
+            let mut descriptor = source.descriptor.clone();
+            descriptor.shader_defs = defs.iter().chain(&source.pass_defs).cloned().collect();
+
+            self.pending_pipelines
+                .retain(|(pending_key, _)| pending_key != &pipeline_key);
+            let defs_key = (
+                pipeline_key.clone(),
+                shader_defs_text(&descriptor.shader_defs),
+            );
+            match self.pipeline_ids_by_defs.get(&defs_key) {
+                // Picked up by the next extraction, right away if it already compiled
+                Some(cached_id) => {
+                    self.cached_pipeline_ids
+                        .insert(pipeline_key.clone(), *cached_id);
+                }
+                None => self
+                    .pending_pipelines
+                    .push((pipeline_key.clone(), descriptor)),
             }
-            Step::Swap(_, _) => panic!("Invalid step"),
+            self.pipelines.insert(pipeline_key, None);
+        }
+
+        // The current results come from the previous defs
+        if self.state != WorkerState::Working {
+            self.state = WorkerState::Created;
         }
+        Ok(())
+    }
+
+    /// Compile the passes running `S` with `defs` in place of [`ComputeShader::shader_defs`].
+    /// In case of error, this function will panic.
+    pub fn set_shader_defs<S: ComputeShader>(&mut self, defs: Vec<ShaderDefVal>) {
+        self.try_set_shader_defs::<S>(defs).unwrap()
     }
 
     /// Add a new uniform buffer to the worker, and fill it with `uniform`. Will replace the old buffer if it exists.
-    pub fn add_uniform<T: ShaderType + WriteInto, E: Debug + Copy>(
+    pub fn add_uniform<T: ShaderType + WriteInto, E: ComputeFields>(
         &mut self,
         render_device: &RenderDevice,
         name: E,
         uniform: &T,
     ) -> &mut Self {
-        T::assert_uniform_compat();
-        let mut buffer = UniformBuffer::new(Vec::new());
-        buffer.write::<T>(uniform).unwrap();
+        let contents = encode_uniform(name.key(), uniform).unwrap();
 
         let old_buffer = self.buffers.insert(
-            format!("{name:?}"),
+            name.key().to_owned(),
             render_device.create_buffer_with_data(&BufferInitDescriptor {
-                label: Some(&format!("{name:?}")),
-                contents: buffer.as_ref(),
+                label: Some(name.key()),
+                contents: &contents,
                 usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
             }),
         );
         if let Some(old_buffer) = old_buffer {
             old_buffer.destroy();
         }
+        self.initial_contents
+            .insert(name.key().to_owned(), contents);
         self
     }
 
     /// Add a new storage buffer to the worker, and fill it with `storage`. It will be read only. Will replace the old buffer if it exists.
-    pub fn add_storage<T: ShaderType + WriteInto, E: Debug + Copy>(
+    pub fn add_storage<T: ShaderType + WriteInto, E: ComputeFields>(
         &mut self,
         render_device: &RenderDevice,
         name: E,
         storage: &T,
     ) -> &mut Self {
-        let mut buffer = StorageBuffer::new(Vec::new());
-        buffer.write::<T>(storage).unwrap();
+        let contents = encode_storage(name.key(), storage).unwrap();
 
         let old_buffer = self.buffers.insert(
-            format!("{name:?}"),
+            name.key().to_owned(),
             render_device.create_buffer_with_data(&BufferInitDescriptor {
-                label: Some(&format!("{name:?}")),
-                contents: buffer.as_ref(),
+                label: Some(name.key()),
+                contents: &contents,
                 usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
             }),
         );
         if let Some(old_buffer) = old_buffer {
             old_buffer.destroy();
         }
+        self.initial_contents
+            .insert(name.key().to_owned(), contents);
         self
     }
 
     /// Add a new read/write storage buffer to the worker, and fill it with `storage`. Will replace the old buffer if it exists.
-    pub fn add_rw_storage<T: ShaderType + WriteInto, E: Debug + Copy>(
+    pub fn add_rw_storage<T: ShaderType + WriteInto, E: ComputeFields>(
         &mut self,
         render_device: &RenderDevice,
         name: E,
         storage: &T,
     ) -> &mut Self {
-        let mut buffer = StorageBuffer::new(Vec::new());
-        buffer.write::<T>(storage).unwrap();
+        let contents = encode_storage(name.key(), storage).unwrap();
 
         let old_buffer = self.buffers.insert(
-            format!("{name:?}"),
+            name.key().to_owned(),
             render_device.create_buffer_with_data(&BufferInitDescriptor {
-                label: Some(&format!("{name:?}")),
-                contents: buffer.as_ref(),
+                label: Some(name.key()),
+                contents: &contents,
                 usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC | BufferUsages::STORAGE,
             }),
         );
         if let Some(old_buffer) = old_buffer {
             old_buffer.destroy();
         }
+        self.initial_contents
+            .insert(name.key().to_owned(), contents);
         self
     }
 
@@ -219,82 +1074,387 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
     /// your shaders.
     /// The buffer will be filled with `data`
     /// Will replace the old buffer if it exists.
-    pub fn add_staging<T: ShaderType + WriteInto, E: Debug + Copy>(
+    pub fn add_staging<T: ShaderType + WriteInto, E: ComputeFields>(
         &mut self,
         render_device: &RenderDevice,
         name: E,
         data: &T,
     ) -> &mut Self {
         self.add_rw_storage(render_device, name, data);
-        let buffer = self.buffers.get(&format!("{name:?}")).unwrap();
+        let buffer = self.buffers.get(name.key()).unwrap();
 
         let staging = StagingBuffer {
             mapped: true,
             buffer: render_device.create_buffer(&BufferDescriptor {
-                label: Some(&format!("{name:?}")),
+                label: Some(name.key()),
                 size: buffer.size(),
                 usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
                 mapped_at_creation: true,
             }),
+            primary: false,
         };
 
-        let old_buffer = self.staging_buffers.insert(format!("{name:?}"), staging);
+        let old_buffer = self.staging_buffers.insert(name.key().to_owned(), staging);
         if let Some(old_buffer) = old_buffer {
             old_buffer.buffer.destroy();
         }
         self
     }
 
-    #[inline]
-    fn dispatch(&mut self, index: usize) -> Result<()> {
-        let compute_pass = match &self.steps[index] {
-            Step::ComputePass(compute_pass) => compute_pass,
-            Step::Swap(_, _) => return Err(Error::InvalidStep(format!("{:?}", self.steps[index]))),
+    /// Replace the buffer behind `target` by one of `size` bytes with the same usages,
+    /// resizing its staging buffer along with it. With `preserve`, the first bytes of the old
+    /// buffer are copied to the new one, as many as fit, otherwise it starts zeroed.
+    /// The copy is recorded in the worker's command encoder: it happens when the next run is
+    /// submitted, before its steps, so writes made to `target` until then are overwritten.
+    /// Swapped buffers must keep the same size, resize them all together.
+    /// Fails while the worker is working, if `size` isn't a non-zero multiple of 4,
+    /// or to preserve the contents of a buffer without the `COPY_SRC` and `COPY_DST` usages.
+    pub fn try_resize_buffer(
+        &mut self,
+        render_device: &RenderDevice,
+        target: W::Fields,
+        size: u64,
+        preserve: bool,
+    ) -> Result<()> {
+        let name = target.key();
+        if self.state == WorkerState::Working {
+            return Err(Error::WorkerBusy);
+        }
+        let Some(old_buffer) = self.buffers.get(name) else {
+            return Err(Error::BufferNotFound(name.to_owned()));
         };
 
-        let mut entries = vec![];
-        for (index, var) in compute_pass.vars.iter().enumerate() {
-            let Some(buffer) = self.buffers.get(var) else {
-                return Err(Error::BufferNotFound(var.to_owned()));
-            };
-
-            let entry = BindGroupEntry {
-                binding: index as u32,
-                resource: buffer.as_entire_binding(),
-            };
+        let usage = old_buffer.usage();
+        let copyable = usage.contains(BufferUsages::COPY_SRC | BufferUsages::COPY_DST);
+        if size == 0 || !size.is_multiple_of(COPY_BUFFER_ALIGNMENT) || (preserve && !copyable) {
+            return Err(Error::InvalidResize(name.to_owned(), size));
+        }
+        // Checked before anything is replaced, the copy is recorded in the encoder
+        if preserve && self.command_encoder.is_none() {
+            return Err(Error::EncoderIsNone);
+        }
+        let kept = old_buffer.size().min(size);
+
+        // A directly mapped buffer can't be copied on the GPU while mapped
+        self.unmap_primary(name);
+
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some(name),
+            size,
+            usage,
+            mapped_at_creation: false,
+        });
+        let old_buffer = self
+            .buffers
+            .insert(name.to_owned(), buffer.clone())
+            .unwrap();
+        if let Some(encoder) = self.command_encoder.as_mut().filter(|_| preserve) {
+            // The encoder keeps the old buffer alive until the copy is submitted
+            encoder.copy_buffer_to_buffer(&old_buffer, 0, &buffer, 0, kept);
+        } else {
+            old_buffer.destroy();
+        }
 
-            entries.push(entry);
+        if let Some(contents) = self.initial_contents.get_mut(name) {
+            contents.resize(size as usize, 0);
         }
 
-        let Some(maybe_pipeline) = self.pipelines.get(&compute_pass.shader_type_path) else {
-            return Err(Error::PipelinesEmpty);
+        let Some(old_staging) = self.staging_buffers.get(name) else {
+            return Ok(());
         };
-
-        let Some(pipeline) = maybe_pipeline else {
-            return Err(Error::PipelineNotReady);
+        let staging = if old_staging.primary {
+            StagingBuffer {
+                mapped: false,
+                buffer,
+                primary: true,
+            }
+        } else {
+            // A staging buffer waiting to be mapped is replaced by one that isn't mapped either
+            let staging_buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some(name),
+                size,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: old_staging.mapped,
+            });
+            if preserve && old_staging.mapped {
+                staging_buffer
+                    .slice(..kept)
+                    .get_mapped_range_mut()
+                    .copy_from_slice(&old_staging.buffer.slice(..kept).get_mapped_range());
+            }
+            old_staging.buffer.destroy();
+            StagingBuffer {
+                mapped: old_staging.mapped,
+                buffer: staging_buffer,
+                primary: false,
+            }
         };
+        self.staging_buffers.insert(name.to_owned(), staging);
+        Ok(())
+    }
 
-        let bind_group_layout = pipeline.get_bind_group_layout(0);
-        let bind_group =
-            self.render_device
-                .create_bind_group(None, &bind_group_layout.into(), &entries);
+    /// Replace the buffer behind `target` by one of `size` bytes, see `try_resize_buffer`.
+    /// In case of error, this function will panic.
+    pub fn resize_buffer(
+        &mut self,
+        render_device: &RenderDevice,
+        target: W::Fields,
+        size: u64,
+        preserve: bool,
+    ) {
+        self.try_resize_buffer(render_device, target, size, preserve)
+            .unwrap()
+    }
+
+    /// The entries binding `vars` in order, to their buffer or texture view.
+    /// The `param` buffer of `instances` is bound one parameter set at a time.
+    fn bind_group_entries(
+        &self,
+        vars: &[String],
+        instances: Option<&Instances>,
+    ) -> Result<Vec<BindGroupEntry<'_>>> {
+        let mut entries = vec![];
+        for (index, var) in vars.iter().enumerate() {
+            let Some(buffer) = self.buffers.get(var) else {
+                let Some(texture) = self.textures.get(var) else {
+                    return Err(Error::BufferNotFound(var.to_owned()));
+                };
+                entries.push(BindGroupEntry {
+                    binding: index as u32,
+                    resource: BindingResource::TextureView(&texture.view),
+                });
+                continue;
+            };
+
+            let instances = instances.filter(|instances| &instances.param == var);
+
+            let resource = match (instances, self.binding_sizes.get(var)) {
+                // Only one parameter set is visible at a time, at the dynamic offset
+                (Some(instances), _) => BindingResource::Buffer(BufferBinding {
+                    buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(instances.stride as u64),
+                }),
+                (None, Some(size)) => BindingResource::Buffer(BufferBinding {
+                    buffer,
+                    offset: 0,
+                    size: NonZeroU64::new((*size).min(buffer.size())),
+                }),
+                (None, None) => buffer.as_entire_binding(),
+            };
+
+            let entry = BindGroupEntry {
+                binding: index as u32,
+                resource,
+            };
+
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    #[inline]
+    fn dispatch(&mut self, index: usize) -> Result<()> {
+        let compute_pass = match &self.steps[index] {
+            Step::ComputePass(compute_pass) => compute_pass,
+            _ => return Err(Error::InvalidStep(format!("{:?}", self.steps[index]))),
+        };
+
+        if !compute_pass.enabled {
+            return Ok(());
+        }
+
+        let dispatch_size = match &compute_pass.dynamic_size {
+            Some(dynamic_size) => dynamic_size.evaluate(self),
+            None => compute_pass.dispatch_size,
+        };
+        check_dispatch_size(&self.render_device, dispatch_size)?;
+
+        // Nothing runs, which is almost always a bug, e.g. a dispatch size computed from zero elements
+        if compute_pass.indirect_count.is_none()
+            && compute_pass.indirect_args.is_none()
+            && dispatch_size.contains(&0)
+            && self.empty_dispatch_warned.insert(index)
+        {
+            warn!(
+                "Pass {} of {} dispatches {dispatch_size:?} workgroups, it won't run",
+                compute_pass
+                    .label
+                    .as_deref()
+                    .unwrap_or(&compute_pass.shader_type_path),
+                std::any::type_name::<W>()
+            );
+        }
+
+        let entries =
+            self.bind_group_entries(&compute_pass.vars, compute_pass.instances.as_ref())?;
+        let mut extra_entries = vec![];
+        for vars in &compute_pass.extra_groups {
+            extra_entries.push(self.bind_group_entries(vars, None)?);
+        }
+
+        let Some(maybe_pipeline) = self.pipelines.get(&compute_pass.pipeline_key) else {
+            return Err(Error::PipelinesEmpty);
+        };
+
+        let Some(pipeline) = maybe_pipeline else {
+            return Err(Error::PipelineNotReady);
+        };
+
+        // Shaders without any binding have no bind group 0 to set
+        let bind_group_layout = if entries.is_empty() {
+            None
+        } else {
+            let Some(bind_group_layout) = self.bind_group_layouts.get(&compute_pass.pipeline_key)
+            else {
+                return Err(Error::PipelineNotReady);
+            };
+            Some(bind_group_layout)
+        };
+
+        if tracing::enabled!(tracing::Level::TRACE) {
+            let bindings = compute_pass
+                .vars
+                .iter()
+                .map(
+                    |var| match (self.buffers.get(var), self.textures.get(var)) {
+                        (Some(buffer), _) => format!("{var}: {} bytes", buffer.size()),
+                        (None, Some(texture)) => format!("{var}: {:?}", texture.texture.size()),
+                        (None, None) => format!("{var}: missing"),
+                    },
+                )
+                .collect::<Vec<_>>();
+            trace!(
+                shader = compute_pass.shader_type_path,
+                ?dispatch_size,
+                ?bindings,
+                "dispatch"
+            );
+        }
+
+        let bind_group = bind_group_layout.map(|bind_group_layout| {
+            self.render_device
+                .create_bind_group(None, bind_group_layout, &entries)
+        });
+
+        // Only group 0 may have an explicit layout, the others are always the pipeline's
+        let extra_bind_groups = extra_entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entries)| !entries.is_empty())
+            .map(|(index, entries)| {
+                let group = index as u32 + 1;
+                let layout = pipeline.get_bind_group_layout(group).into();
+                let bind_group = self.render_device.create_bind_group(None, &layout, entries);
+                (group, bind_group)
+            })
+            .collect::<Vec<_>>();
+
+        let count_bind_group = match &compute_pass.indirect_count {
+            Some(indirect_count) => {
+                let Some(count) = self.buffers.get(&indirect_count.count) else {
+                    return Err(Error::BufferNotFound(indirect_count.count.to_owned()));
+                };
+                Some(self.render_device.create_bind_group(
+                    None,
+                    &indirect_count.layout,
+                    &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: count.as_entire_binding(),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: indirect_count.args.as_entire_binding(),
+                        },
+                    ],
+                ))
+            }
+            None => None,
+        };
+
+        let indirect_args = match &compute_pass.indirect_args {
+            Some((name, offset)) => match self.buffers.get(name) {
+                Some(buffer) => Some((buffer, *offset)),
+                None => return Err(Error::BufferNotFound(name.to_owned())),
+            },
+            None => None,
+        };
+
+        let push_constants = self.push_constants.get(&compute_pass.shader_type_path);
+
+        let pass_name = compute_pass
+            .label
+            .as_deref()
+            .unwrap_or(&compute_pass.shader_type_path);
+        let timestamp_index = self
+            .timestamps
+            .as_mut()
+            .and_then(|timestamps| timestamps.push_pass(pass_name));
 
         let Some(encoder) = &mut self.command_encoder else {
             return Err(Error::EncoderIsNone);
         };
+        encoder.push_debug_group(pass_name);
+        if let (Some(indirect_count), Some(count_bind_group)) =
+            (&compute_pass.indirect_count, &count_bind_group)
         {
             let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: None,
+                label: Some("count_to_dispatch_args"),
                 timestamp_writes: None,
             });
+            cpass.set_pipeline(&indirect_count.pipeline);
+            cpass.set_bind_group(0, count_bind_group, &[]);
+            cpass.dispatch_workgroups(1, 1, 1);
+        }
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: compute_pass.label.as_deref(),
+                timestamp_writes: self
+                    .timestamps
+                    .as_ref()
+                    .zip(timestamp_index)
+                    .map(|(timestamps, index)| timestamps.writes(index)),
+            });
             cpass.set_pipeline(pipeline);
-            cpass.set_bind_group(0, &bind_group, &[]);
-            cpass.dispatch_workgroups(
-                compute_pass.dispatch_size[0],
-                compute_pass.dispatch_size[1],
-                compute_pass.dispatch_size[2],
-            )
+            if let Some(push_constants) = push_constants {
+                cpass.set_push_constants(0, push_constants);
+            }
+            for (group, bind_group) in &extra_bind_groups {
+                cpass.set_bind_group(*group, bind_group, &[]);
+            }
+            match (&bind_group, &compute_pass.instances) {
+                (Some(bind_group), Some(instances)) => {
+                    for i in 0..instances.count {
+                        cpass.set_bind_group(0, bind_group, &[i * instances.stride]);
+                        cpass.dispatch_workgroups(
+                            dispatch_size[0],
+                            dispatch_size[1],
+                            dispatch_size[2],
+                        );
+                    }
+                }
+                (bind_group, _) => {
+                    if let Some(bind_group) = bind_group {
+                        cpass.set_bind_group(0, bind_group, &[]);
+                    }
+                    match (&compute_pass.indirect_count, indirect_args) {
+                        (Some(indirect_count), _) => {
+                            cpass.dispatch_workgroups_indirect(&indirect_count.args, 0)
+                        }
+                        (None, Some((buffer, offset))) => {
+                            cpass.dispatch_workgroups_indirect(buffer, offset)
+                        }
+                        (None, None) => cpass.dispatch_workgroups(
+                            dispatch_size[0],
+                            dispatch_size[1],
+                            dispatch_size[2],
+                        ),
+                    }
+                }
+            }
         }
+        encoder.pop_debug_group();
 
         Ok(())
     }
@@ -302,10 +1462,8 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
     #[inline]
     fn swap(&mut self, index: usize) -> Result<()> {
         let (buf_a_name, buf_b_name) = match &self.steps[index] {
-            Step::ComputePass(_) => {
-                return Err(Error::InvalidStep(format!("{:?}", self.steps[index])))
-            }
             Step::Swap(a, b) => (a.as_str(), b.as_str()),
+            _ => return Err(Error::InvalidStep(format!("{:?}", self.steps[index]))),
         };
 
         if !self.buffers.contains_key(buf_a_name) {
@@ -322,9 +1480,83 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
         Ok(())
     }
 
+    #[inline]
+    fn clear(&mut self, index: usize) -> Result<()> {
+        let Step::Clear(name) = &self.steps[index] else {
+            return Err(Error::InvalidStep(format!("{:?}", self.steps[index])));
+        };
+
+        let Some(buffer) = self.buffers.get(name) else {
+            return Err(Error::BufferNotFound(name.to_owned()));
+        };
+        let Some(encoder) = &mut self.command_encoder else {
+            return Err(Error::EncoderIsNone);
+        };
+
+        encoder.clear_buffer(buffer, 0, None);
+        Ok(())
+    }
+
+    #[inline]
+    fn custom(&mut self, index: usize) -> Result<()> {
+        let Step::Custom(custom) = &self.steps[index] else {
+            return Err(Error::InvalidStep(format!("{:?}", self.steps[index])));
+        };
+
+        let Some(encoder) = &mut self.command_encoder else {
+            return Err(Error::EncoderIsNone);
+        };
+
+        (custom.0)(encoder, &self.buffers);
+        Ok(())
+    }
+
+    #[inline]
+    fn rotate(&mut self, index: usize) -> Result<()> {
+        let names = match &self.steps[index] {
+            Step::Rotate(names) => names,
+            _ => return Err(Error::InvalidStep(format!("{:?}", self.steps[index]))),
+        };
+
+        if let Some(name) = names.iter().find(|name| !self.buffers.contains_key(*name)) {
+            return Err(Error::BufferNotFound(name.to_owned()));
+        }
+
+        let rotated: Vec<Buffer> = names
+            .iter()
+            .map(|name| self.buffers.remove(name).unwrap())
+            .collect();
+
+        for (i, buffer) in rotated.into_iter().enumerate() {
+            self.buffers
+                .insert(names[(i + 1) % names.len()].clone(), buffer);
+        }
+
+        Ok(())
+    }
+
     #[inline]
     fn read_staging_buffers(&mut self) -> Result<&mut Self> {
+        // Query results must land in their buffers before those are copied to staging buffers
+        if let (Some(timestamps), Some(encoder)) = (&self.timestamps, &mut self.command_encoder) {
+            timestamps.resolve(encoder);
+        }
+        for (name, (query_set, count)) in &self.query_sets {
+            let Some(encoder) = &mut self.command_encoder else {
+                return Err(Error::EncoderIsNone);
+            };
+            let Some(buffer) = self.buffers.get(name) else {
+                return Err(Error::BufferNotFound(name.to_owned()));
+            };
+
+            encoder.resolve_query_set(query_set, 0..*count, buffer, 0);
+        }
+
         for (name, staging_buffer) in &self.staging_buffers {
+            if staging_buffer.primary {
+                continue;
+            }
+
             let Some(encoder) = &mut self.command_encoder else {
                 return Err(Error::EncoderIsNone);
             };
@@ -345,131 +1577,1404 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
 
     #[inline]
     fn map_staging_buffers(&mut self) -> &mut Self {
+        // The submitted work itself, then every staging buffer mapping
+        self.completion = Completion::new(
+            self.staging_buffers.len() + 1,
+            self.completion_senders.clone(),
+        );
+
+        let completion = self.completion.clone();
+        self.render_queue
+            .on_submitted_work_done(move || completion.complete_work());
+
         for (_, staging_buffer) in self.staging_buffers.iter_mut() {
             let read_buffer_slice = staging_buffer.buffer.slice(..);
 
-            read_buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-                let err = result.err();
-                if err.is_some() {
-                    let some_err = err.unwrap();
-                    panic!("{}", some_err.to_string());
-                }
-            });
+            let completion = self.completion.clone();
+            read_buffer_slice.map_async(wgpu::MapMode::Read, move |result| match result {
+                Ok(()) => completion.complete_one(),
+                Err(err) => completion.fail(err.to_string()),
+            });
+
+            staging_buffer.mapped = true;
+        }
+        self
+    }
+
+    /// Read data from `target` staging buffer, return raw bytes
+    ///
+    /// Read/write storage buffers without a staging buffer can be read too: they are copied
+    /// to a temporary buffer, blocking until the GPU is done. Prefer `add_staging` for buffers read often.
+    #[inline]
+    pub fn try_read_raw<'a>(&'a self, target: W::Fields) -> Result<impl Deref<Target = [u8]> + 'a> {
+        let name = target.key().to_owned();
+
+        if let Some(staging_buffer) = self.staging_buffers.get(&name) {
+            self.check_mapped(&name, staging_buffer)?;
+
+            return Ok(RawData::Mapped(
+                staging_buffer.buffer.slice(..).get_mapped_range(),
+            ));
+        }
+
+        match self.buffers.get(&name) {
+            Some(buffer) if buffer.usage().contains(BufferUsages::COPY_SRC) => Ok(RawData::Copied(
+                read_buffer_blocking(&self.render_device, &self.render_queue, buffer)?,
+            )),
+            _ => Err(Error::StagingBufferNotFound(name)),
+        }
+    }
+
+    /// Try Read `byte_len` bytes of `target` from `byte_offset`, return raw bytes.
+    /// Only this range is mapped, or copied for buffers without a staging buffer.
+    pub fn try_read_raw_range<'a>(
+        &'a self,
+        target: W::Fields,
+        byte_offset: u64,
+        byte_len: u64,
+    ) -> Result<impl Deref<Target = [u8]> + 'a> {
+        let name = target.key().to_owned();
+        let end = byte_offset.checked_add(byte_len);
+
+        if let Some(staging_buffer) = self.staging_buffers.get(&name) {
+            if end.is_none_or(|end| end > staging_buffer.buffer.size()) {
+                return Err(Error::OutOfBounds(name, byte_offset, byte_len));
+            }
+            self.check_mapped(&name, staging_buffer)?;
+            if byte_len == 0 {
+                return Ok(RawData::Copied(Vec::new()));
+            }
+
+            // Mapped ranges must be aligned, map the smallest aligned range around the bytes
+            let map_start = byte_offset / MAP_ALIGNMENT * MAP_ALIGNMENT;
+            let map_end = (byte_offset + byte_len)
+                .next_multiple_of(COPY_BUFFER_ALIGNMENT)
+                .min(staging_buffer.buffer.size());
+            let start = (byte_offset - map_start) as usize;
+            return Ok(RawData::MappedPart(
+                staging_buffer
+                    .buffer
+                    .slice(map_start..map_end)
+                    .get_mapped_range(),
+                start..start + byte_len as usize,
+            ));
+        }
+
+        match self.buffers.get(&name) {
+            Some(buffer) if buffer.usage().contains(BufferUsages::COPY_SRC) => {
+                if end.is_none_or(|end| end > buffer.size()) {
+                    return Err(Error::OutOfBounds(name, byte_offset, byte_len));
+                }
+                if byte_len == 0 {
+                    return Ok(RawData::Copied(Vec::new()));
+                }
+
+                // Copies must be aligned, copy the smallest aligned range around the bytes
+                let copy_start = byte_offset / COPY_BUFFER_ALIGNMENT * COPY_BUFFER_ALIGNMENT;
+                let copy_end = (byte_offset + byte_len)
+                    .next_multiple_of(COPY_BUFFER_ALIGNMENT)
+                    .min(buffer.size());
+                let mut bytes = read_buffer_range_blocking(
+                    &self.render_device,
+                    &self.render_queue,
+                    buffer,
+                    copy_start..copy_end,
+                )?;
+                bytes.drain(..(byte_offset - copy_start) as usize);
+                bytes.truncate(byte_len as usize);
+                Ok(RawData::Copied(bytes))
+            }
+            _ => Err(Error::StagingBufferNotFound(name)),
+        }
+    }
+
+    /// Try Read `byte_len` bytes of `target` from `byte_offset`, return raw bytes.
+    /// In case of error, this function will panic.
+    pub fn read_raw_range<'a>(
+        &'a self,
+        target: W::Fields,
+        byte_offset: u64,
+        byte_len: u64,
+    ) -> impl Deref<Target = [u8]> + 'a {
+        self.try_read_raw_range(target, byte_offset, byte_len)
+            .unwrap()
+    }
+
+    /// Check the results of the last run can be read from `staging_buffer`.
+    fn check_mapped(&self, name: &str, staging_buffer: &StagingBuffer) -> Result<()> {
+        // Mapping completes once the GPU is done with the run
+        let map_pending = self.state == WorkerState::Working && !self.completion.is_done();
+        if !staging_buffer.mapped || map_pending {
+            return Err(Error::BufferNotMapped(name.to_owned()));
+        }
+        if let Some(message) = self.completion.map_error() {
+            return Err(Error::MapFailed(message));
+        }
+        Ok(())
+    }
+
+    /// Read data from `target` staging buffer, return raw bytes
+    /// Panics on error.
+    #[inline]
+    pub fn read_raw<'a>(&'a self, target: W::Fields) -> impl Deref<Target = [u8]> + 'a {
+        self.try_read_raw(target).unwrap()
+    }
+
+    /// Read every staging buffer of `targets` at once, return their raw bytes in the same order.
+    /// Nothing is mapped unless all of them can be read. Each target may only be listed once,
+    /// as a buffer can't be viewed twice at the same time.
+    pub fn try_read_many<'a>(
+        &'a self,
+        targets: &[W::Fields],
+    ) -> Result<Vec<impl Deref<Target = [u8]> + 'a>> {
+        let mut staging_buffers = Vec::with_capacity(targets.len());
+        let mut seen = HashSet::default();
+        for target in targets {
+            let name = target.key();
+            if !seen.insert(name) {
+                return Err(Error::DuplicateField(name.to_owned()));
+            }
+            let Some(staging_buffer) = self.staging_buffers.get(name) else {
+                return Err(Error::StagingBufferNotFound(name.to_owned()));
+            };
+            self.check_mapped(name, staging_buffer)?;
+            staging_buffers.push(staging_buffer);
+        }
+
+        Ok(staging_buffers
+            .into_iter()
+            .map(|staging_buffer| {
+                RawData::Mapped(staging_buffer.buffer.slice(..).get_mapped_range())
+            })
+            .collect())
+    }
+
+    /// Read every staging buffer of `targets` at once, return their raw bytes in the same order.
+    /// In case of error, this function will panic.
+    pub fn read_many<'a>(&'a self, targets: &[W::Fields]) -> Vec<impl Deref<Target = [u8]> + 'a> {
+        self.try_read_many(targets).unwrap()
+    }
+
+    /// Copy `target` texture back and return its texels, rows and layers one after the other
+    /// without padding. The texture needs the `COPY_SRC` usage, and a format with a single
+    /// block size, so not a depth-stencil one. This blocks until the GPU is done.
+    pub fn try_read_texture(&self, target: W::Fields) -> Result<Vec<u8>> {
+        let name = target.key();
+        let Some(texture) = self.textures.get(name) else {
+            return Err(Error::ImageNotFound(name.to_owned()));
+        };
+        read_texture_blocking(
+            &self.render_device,
+            &self.render_queue,
+            name,
+            &texture.texture,
+        )
+    }
+
+    /// Copy `target` texture back and return its texels.
+    /// In case of error, this function will panic.
+    pub fn read_texture(&self, target: W::Fields) -> Vec<u8> {
+        self.try_read_texture(target).unwrap()
+    }
+
+    /// Read every staging buffer at once, return raw bytes keyed by field name.
+    /// All fields come from the same completed run.
+    /// Nothing is read unless every staging buffer can be, like `try_read_many`.
+    pub fn try_read_all(&self) -> Result<HashMap<String, Vec<u8>>> {
+        for (name, staging_buffer) in &self.staging_buffers {
+            self.check_mapped(name, staging_buffer)?;
+        }
+
+        Ok(self
+            .staging_buffers
+            .iter()
+            .map(|(name, staging_buffer)| {
+                let bytes = staging_buffer.buffer.slice(..).get_mapped_range().to_vec();
+                (name.clone(), bytes)
+            })
+            .collect())
+    }
+
+    /// Read every staging buffer at once, return raw bytes keyed by field name.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn read_all(&self) -> HashMap<String, Vec<u8>> {
+        self.try_read_all().unwrap()
+    }
+
+    /// Try Read data from `target` staging buffer, return a single `B: Pod`
+    #[inline]
+    pub fn try_read<B: AnyBitPattern>(&self, target: W::Fields) -> Result<B> {
+        let result = *from_bytes::<B>(&self.try_read_raw(target)?);
+        Ok(result)
+    }
+
+    /// Try Read data from `target` staging buffer, return a single `B: Pod`
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn read<B: AnyBitPattern>(&self, target: W::Fields) -> B {
+        self.try_read(target).unwrap()
+    }
+
+    /// Try to read the atomic `u32` counter at the start of `target`, added with `add_atomic_counter`.
+    pub fn try_read_counter(&self, target: W::Fields) -> Result<u32> {
+        let bytes = self.try_read_raw(target)?;
+        match bytes.get(..4) {
+            Some(counter) => Ok(pod_read_unaligned(counter)),
+            None => Err(Error::IndexOutOfBounds(target.key().to_owned(), 0)),
+        }
+    }
+
+    /// Read the atomic `u32` counter at the start of `target`, added with `add_atomic_counter`.
+    /// In case of error, this function will panic.
+    pub fn read_counter(&self, target: W::Fields) -> u32 {
+        self.try_read_counter(target).unwrap()
+    }
+
+    /// Set the counter `target` back to zero on the GPU before the next run,
+    /// so it only counts what that run emits.
+    pub fn try_reset_counter(&mut self, target: W::Fields) -> Result<()> {
+        self.try_clear_buffer(target)
+    }
+
+    /// Set the counter `target` back to zero on the GPU before the next run.
+    /// In case of error, this function will panic.
+    pub fn reset_counter(&mut self, target: W::Fields) {
+        self.try_reset_counter(target).unwrap()
+    }
+
+    /// Try Read the `index`-th element of `target`, return a single `B: Pod`.
+    /// Only this element is read: it is sliced out of the mapped staging buffer,
+    /// or copied alone from a buffer without one.
+    pub fn try_read_one<B: AnyBitPattern>(&self, target: W::Fields, index: usize) -> Result<B> {
+        let name = target.key().to_owned();
+        let size = std::mem::size_of::<B>();
+        let start = index * size;
+
+        if !self.staging_buffers.contains_key(&name) {
+            if let Some(buffer) = self
+                .buffers
+                .get(&name)
+                .filter(|buffer| buffer.usage().contains(BufferUsages::COPY_SRC))
+            {
+                if (start + size) as u64 > buffer.size() {
+                    return Err(Error::IndexOutOfBounds(name, index));
+                }
+
+                // Copies must be aligned, copy the smallest aligned range around the element
+                let copy_start = start as u64 / COPY_BUFFER_ALIGNMENT * COPY_BUFFER_ALIGNMENT;
+                let copy_end = ((start + size) as u64)
+                    .next_multiple_of(COPY_BUFFER_ALIGNMENT)
+                    .min(buffer.size());
+                let bytes = read_buffer_range_blocking(
+                    &self.render_device,
+                    &self.render_queue,
+                    buffer,
+                    copy_start..copy_end,
+                )?;
+                let offset = start - copy_start as usize;
+                return Ok(pod_read_unaligned(&bytes[offset..offset + size]));
+            }
+        }
+
+        let bytes = self.try_read_raw(target)?;
+        match bytes.get(start..start + size) {
+            Some(element) => Ok(pod_read_unaligned(element)),
+            None => Err(Error::IndexOutOfBounds(name, index)),
+        }
+    }
+
+    /// Try Read the `index`-th element of `target`, return a single `B: Pod`.
+    /// In case of error, this function will panic.
+    pub fn read_one<B: AnyBitPattern>(&self, target: W::Fields, index: usize) -> B {
+        self.try_read_one(target, index).unwrap()
+    }
+
+    /// Try Read data from `target` staging buffer, return a vector of `B: Pod`
+    #[inline]
+    pub fn try_read_vec<B: AnyBitPattern>(&self, target: W::Fields) -> Result<Vec<B>> {
+        let bytes = self.try_read_raw(target)?;
+        Ok(cast_slice::<u8, B>(&bytes).to_vec())
+    }
+
+    /// Try Read data from `target` staging buffer, return a vector of `B: Pod`
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn read_vec<B: AnyBitPattern>(&self, target: W::Fields) -> Vec<B> {
+        self.try_read_vec(target).unwrap()
+    }
+
+    /// Try Read `count` elements of `target` from the `start_elem`-th, return a vector of `B: Pod`.
+    /// Only these elements are mapped, see [`try_read_raw_range`](Self::try_read_raw_range).
+    pub fn try_read_vec_range<B: AnyBitPattern>(
+        &self,
+        target: W::Fields,
+        start_elem: usize,
+        count: usize,
+    ) -> Result<Vec<B>> {
+        let size = std::mem::size_of::<B>();
+        let bytes =
+            self.try_read_raw_range(target, (start_elem * size) as u64, (count * size) as u64)?;
+        Ok(bytes.chunks_exact(size).map(pod_read_unaligned).collect())
+    }
+
+    /// Try Read `count` elements of `target` from the `start_elem`-th, return a vector of `B: Pod`.
+    /// In case of error, this function will panic.
+    pub fn read_vec_range<B: AnyBitPattern>(
+        &self,
+        target: W::Fields,
+        start_elem: usize,
+        count: usize,
+    ) -> Vec<B> {
+        self.try_read_vec_range(target, start_elem, count).unwrap()
+    }
+
+    /// Reduce every element of `target`, a storage buffer of `T`, to a single value with `op`,
+    /// e.g. to sum them. This runs a parallel reduction shared by the crate, and blocks
+    /// until the GPU is done. Fails if `target` holds no element.
+    pub fn try_reduce<T: ReduceElement>(&mut self, target: W::Fields, op: ReduceOp) -> Result<T> {
+        let name = target.key().to_owned();
+        let Some(buffer) = self.buffers.get(&name) else {
+            return Err(Error::BufferNotFound(name));
+        };
+        check_reducible::<T>(&name, buffer)?;
+
+        let pipeline = self
+            .reduce_pipelines
+            .entry(format!("{} {op:?}", T::WGSL_TYPE))
+            .or_insert_with(|| reduce_pipeline::<T>(&self.render_device, op));
+
+        reduce_blocking(&self.render_device, &self.render_queue, pipeline, buffer)
+    }
+
+    /// Reduce every element of `target`, a storage buffer of `T`, to a single value with `op`.
+    /// In case of error, this function will panic.
+    pub fn reduce<T: ReduceElement>(&mut self, target: W::Fields, op: ReduceOp) -> T {
+        self.try_reduce(target, op).unwrap()
+    }
+
+    /// Try Read a grid of `width` x `height` elements from `target`, whose rows start every
+    /// `row_stride` bytes, e.g. 256 bytes aligned rows copied from a texture.
+    /// Return the rows one after the other, without their padding.
+    /// For volumes, read every layer as rows of one big grid.
+    pub fn try_read_grid<B: AnyBitPattern>(
+        &self,
+        target: W::Fields,
+        width: usize,
+        height: usize,
+        row_stride: usize,
+    ) -> Result<Vec<B>> {
+        let row_size = width * std::mem::size_of::<B>();
+        let bytes = self.try_read_raw(target)?;
+
+        let fits = height == 0 || (height - 1) * row_stride + row_size <= bytes.len();
+        if row_stride < row_size || !fits {
+            return Err(Error::InvalidGrid(target.key().to_owned()));
+        }
+
+        let mut grid = Vec::with_capacity(width * height);
+        for row in 0..height {
+            let start = row * row_stride;
+            grid.extend(
+                bytes[start..start + row_size]
+                    .chunks_exact(std::mem::size_of::<B>())
+                    .map(pod_read_unaligned::<B>),
+            );
+        }
+        Ok(grid)
+    }
+
+    /// Try Read a grid of `width` x `height` elements from `target`, whose rows start every
+    /// `row_stride` bytes. Return the rows one after the other, without their padding.
+    /// In case of error, this function will panic.
+    pub fn read_grid<B: AnyBitPattern>(
+        &self,
+        target: W::Fields,
+        width: usize,
+        height: usize,
+        row_stride: usize,
+    ) -> Vec<B> {
+        self.try_read_grid(target, width, height, row_stride)
+            .unwrap()
+    }
+
+    /// Try Read data from `target` staging buffer, return a `T: ShaderType`
+    /// The data is decoded with the storage layout, honoring its padding,
+    /// so it is the counterpart of `add_staging`.
+    #[inline]
+    pub fn try_read_encase<T: ShaderType + CreateFrom>(&self, target: W::Fields) -> Result<T> {
+        let bytes = self.try_read_raw(target)?;
+        StorageBuffer::new(&*bytes)
+            .create()
+            .map_err(|err| Error::Encase {
+                field: target.key().to_owned(),
+                message: err.to_string(),
+            })
+    }
+
+    /// Try Read data from `target` staging buffer, return a `T: ShaderType`
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn read_encase<T: ShaderType + CreateFrom>(&self, target: W::Fields) -> T {
+        self.try_read_encase(target).unwrap()
+    }
+
+    /// Try Read data from `target` staging buffer, return a vector of `T: ShaderType`
+    /// Each element is decoded with the storage layout, so types with padding,
+    /// like structs holding a `Vec3`, are read back correctly.
+    #[inline]
+    pub fn try_read_encase_vec<T: ShaderType + ShaderSize + CreateFrom>(
+        &self,
+        target: W::Fields,
+    ) -> Result<Vec<T>> {
+        self.try_read_encase::<Vec<T>>(target)
+    }
+
+    /// Try Read data from `target` staging buffer, return a vector of `T: ShaderType`
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn read_encase_vec<T: ShaderType + ShaderSize + CreateFrom>(
+        &self,
+        target: W::Fields,
+    ) -> Vec<T> {
+        self.try_read_encase_vec(target).unwrap()
+    }
+
+    /// Write the content of every buffer to `dir/<field>.bin`, for offline inspection.
+    /// Buffers are copied to temporary buffers, so this blocks until the GPU is done.
+    /// Only buffers the GPU can copy from are written, which excludes uniforms and read only storages.
+    pub fn dump_buffers(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir).map_err(Error::Io)?;
+
+        for (name, buffer) in &self.buffers {
+            if !buffer.usage().contains(BufferUsages::COPY_SRC) {
+                continue;
+            }
+
+            let bytes = match self.mapped_primary(name) {
+                Some(bytes) => bytes.to_vec(),
+                None => read_buffer_blocking(&self.render_device, &self.render_queue, buffer)?,
+            };
+            fs::write(dir.join(format!("{name}.bin")), bytes).map_err(Error::Io)?;
+        }
+
+        Ok(())
+    }
+
+    /// Duplicate this worker, copying the current content of its buffers into new allocations,
+    /// so both workers can then diverge, e.g. to compare two runs of a simulation.
+    ///
+    /// Buffers the GPU can't copy from, like uniforms and read only storages, start from
+    /// the data they were created with. Buffers sharing memory, like scratch buffers,
+    /// keep sharing it in the new worker, and so do textures.
+    /// `on_field_ready` and `on_complete` callbacks aren't carried over.
+    /// This blocks until the GPU is done.
+    pub fn fork(&self) -> Self {
+        let mut encoder = self
+            .render_device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+
+        let mut new_buffers: HashMap<BufferId, Buffer> = HashMap::default();
+        let mut buffers = HashMap::default();
+        for (name, buffer) in &self.buffers {
+            let new_buffer = new_buffers.entry(buffer.id()).or_insert_with(|| {
+                let new_buffer = self.render_device.create_buffer(&BufferDescriptor {
+                    label: Some(name),
+                    size: buffer.size(),
+                    usage: buffer.usage(),
+                    mapped_at_creation: false,
+                });
+
+                if let Some(bytes) = self.mapped_primary(name) {
+                    self.render_queue.write_buffer(&new_buffer, 0, &bytes);
+                } else if buffer
+                    .usage()
+                    .contains(BufferUsages::COPY_SRC | BufferUsages::COPY_DST)
+                {
+                    encoder.copy_buffer_to_buffer(buffer, 0, &new_buffer, 0, buffer.size());
+                } else if let (Some(contents), true) = (
+                    self.initial_contents.get(name),
+                    buffer.usage().contains(BufferUsages::COPY_DST),
+                ) {
+                    self.render_queue.write_buffer(&new_buffer, 0, contents);
+                }
+                new_buffer
+            });
+            buffers.insert(name.clone(), new_buffer.clone());
+        }
+
+        self.render_queue.submit(Some(encoder.finish()));
+        self.render_device
+            .wgpu_device()
+            .poll(wgpu::MaintainBase::Wait);
+
+        let staging_buffers = self
+            .staging_buffers
+            .iter()
+            .map(|(name, staging_buffer)| {
+                let buffer = self.render_device.create_buffer(&BufferDescriptor {
+                    label: Some(name),
+                    size: staging_buffer.buffer.size(),
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: true,
+                });
+
+                // Staging buffers can't be copied on the GPU, but their last read data is mapped
+                if staging_buffer.mapped && self.state != WorkerState::Working {
+                    buffer
+                        .slice(..)
+                        .get_mapped_range_mut()
+                        .copy_from_slice(&staging_buffer.buffer.slice(..).get_mapped_range());
+                }
+
+                let staging = StagingBuffer {
+                    mapped: true,
+                    buffer,
+                    primary: false,
+                };
+                (name.clone(), staging)
+            })
+            .collect();
+
+        let state = if self.ready() {
+            WorkerState::FinishedWorking
+        } else {
+            WorkerState::Created
+        };
+
+        Self {
+            state,
+            render_device: self.render_device.clone(),
+            render_queue: self.render_queue.clone(),
+            cached_pipeline_ids: self.cached_pipeline_ids.clone(),
+            pipeline_ids_by_defs: self.pipeline_ids_by_defs.clone(),
+            pipelines: self.pipelines.clone(),
+            bind_group_layouts: self.bind_group_layouts.clone(),
+            reflected_layouts: self.reflected_layouts.clone(),
+            pipeline_errors: self.pipeline_errors.clone(),
+            buffers,
+            textures: self.textures.clone(),
+            initial_contents: self.initial_contents.clone(),
+            // The fork owns copies of every buffer
+            existing_buffers: HashSet::default(),
+            binding_sizes: self.binding_sizes.clone(),
+            staging_buffers,
+            query_sets: self.query_sets.clone(),
+            timestamps: self
+                .timestamps
+                .as_ref()
+                .and_then(|_| PassTimestamps::new(&self.render_device)),
+            steps: self.steps.clone(),
+            command_encoder: Some(
+                self.render_device
+                    .create_command_encoder(&CommandEncoderDescriptor { label: None }),
+            ),
+            pre_run_commands: None,
+            buffers_before_run: HashMap::default(),
+            run_mode: self.run_mode,
+            wait_mode: self.wait_mode,
+            manual_unmap: self.manual_unmap,
+            submit_every: self.submit_every,
+            last_gpu_error: None,
+            last_error: None,
+            completion: Completion::new(0, Vec::new()),
+            field_callbacks: HashMap::default(),
+            complete_callbacks: Vec::new(),
+            completion_senders: Vec::new(),
+            pending_writes: self.pending_writes.clone(),
+            reduce_pipelines: self.reduce_pipelines.clone(),
+            push_constants: self.push_constants.clone(),
+            last_submission: None,
+            frames_in_flight: 0,
+            frame: 0,
+            max_frames_in_flight: self.max_frames_in_flight,
+            empty_dispatch_warned: HashSet::default(),
+            pipeline_sources: self.pipeline_sources.clone(),
+            workgroup_sizes: self.workgroup_sizes.clone(),
+            pending_pipelines: self.pending_pipelines.clone(),
+            batched: false,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Write data to `target` buffer.
+    #[inline]
+    pub fn try_write<T: NoUninit>(&mut self, target: W::Fields, data: &T) -> Result<()> {
+        self.write_bytes(target.key().to_owned(), 0, bytes_of(data))
+    }
+
+    /// Write data to `target` buffer.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn write<T: NoUninit>(&mut self, target: W::Fields, data: &T) {
+        self.try_write(target, data).unwrap()
+    }
+
+    /// Write data to `target` buffer.
+    #[inline]
+    pub fn try_write_slice<T: NoUninit>(&mut self, target: W::Fields, data: &[T]) -> Result<()> {
+        self.write_bytes(target.key().to_owned(), 0, cast_slice(data))
+    }
+
+    /// Write data to `target` buffer.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn write_slice<T: NoUninit>(&mut self, target: W::Fields, data: &[T]) {
+        self.try_write_slice(target, data).unwrap()
+    }
+
+    /// Write `uniform` to `target` buffer, with the same layout as `add_uniform`.
+    #[inline]
+    pub fn try_write_uniform<T: ShaderType + WriteInto>(
+        &mut self,
+        target: W::Fields,
+        uniform: &T,
+    ) -> Result<()> {
+        let bytes = encode_uniform(target.key(), uniform)?;
+        self.write_bytes(target.key().to_owned(), 0, &bytes)
+    }
+
+    /// Write `uniform` to `target` buffer, with the same layout as `add_uniform`.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn write_uniform<T: ShaderType + WriteInto>(&mut self, target: W::Fields, uniform: &T) {
+        self.try_write_uniform(target, uniform).unwrap()
+    }
+
+    /// Write `storage` to `target` buffer, with the same layout as `add_storage`.
+    #[inline]
+    pub fn try_write_storage<T: ShaderType + WriteInto>(
+        &mut self,
+        target: W::Fields,
+        storage: &T,
+    ) -> Result<()> {
+        let bytes = encode_storage(target.key(), storage)?;
+        self.write_bytes(target.key().to_owned(), 0, &bytes)
+    }
+
+    /// Write `storage` to `target` buffer, with the same layout as `add_storage`.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn write_storage<T: ShaderType + WriteInto>(&mut self, target: W::Fields, storage: &T) {
+        self.try_write_storage(target, storage).unwrap()
+    }
+
+    /// Write `data` to `target` buffer, starting `byte_offset` bytes in.
+    /// The rest of the buffer is left untouched. Offset and size must be multiples of 4,
+    /// else [`Error::MisalignedWrite`] is returned.
+    #[inline]
+    pub fn try_write_offset<T: NoUninit>(
+        &mut self,
+        target: W::Fields,
+        byte_offset: u64,
+        data: &T,
+    ) -> Result<()> {
+        self.write_bytes(target.key().to_owned(), byte_offset, bytes_of(data))
+    }
+
+    /// Write `data` to `target` buffer, starting `byte_offset` bytes in.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn write_offset<T: NoUninit>(&mut self, target: W::Fields, byte_offset: u64, data: &T) {
+        self.try_write_offset(target, byte_offset, data).unwrap()
+    }
+
+    /// Write `data` to `target` buffer, starting at its `elem_offset`-th element.
+    /// The rest of the buffer is left untouched. Offset and size in bytes must be multiples of 4.
+    #[inline]
+    pub fn try_write_slice_offset<T: NoUninit>(
+        &mut self,
+        target: W::Fields,
+        elem_offset: usize,
+        data: &[T],
+    ) -> Result<()> {
+        let byte_offset = (elem_offset * std::mem::size_of::<T>()) as u64;
+        self.write_bytes(target.key().to_owned(), byte_offset, cast_slice(data))
+    }
+
+    /// Write `data` to `target` buffer, starting at its `elem_offset`-th element.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn write_slice_offset<T: NoUninit>(
+        &mut self,
+        target: W::Fields,
+        elem_offset: usize,
+        data: &[T],
+    ) {
+        self.try_write_slice_offset(target, elem_offset, data)
+            .unwrap()
+    }
+
+    /// Write `bytes` to buffer `name`, `offset` bytes in.
+    /// Before the first run, writes to a buffer the steps use but that doesn't exist yet
+    /// are deferred until the run, so systems may write before the worker has all its buffers.
+    fn write_bytes(&mut self, name: String, offset: u64, bytes: &[u8]) -> Result<()> {
+        self.unmap_primary(&name);
+        match self.buffers.get(&name) {
+            Some(buffer) => {
+                check_write_bounds(&name, buffer, offset, bytes)?;
+                self.render_queue.write_buffer(buffer, offset, bytes)
+            }
+            None if self.state == WorkerState::Created && self.uses_buffer(&name) => {
+                check_write_alignment(&name, offset, bytes)?;
+                self.pending_writes.push((name, offset, bytes.to_vec()))
+            }
+            None => return Err(Error::BufferNotFound(name)),
+        }
+        Ok(())
+    }
+
+    /// Whether a step binds, swaps or clears the buffer `name`.
+    fn uses_buffer(&self, name: &str) -> bool {
+        self.steps.iter().any(|step| match step {
+            Step::ComputePass(compute_pass) => {
+                compute_pass
+                    .vars
+                    .iter()
+                    .chain(compute_pass.extra_groups.iter().flatten())
+                    .any(|var| var == name)
+                    || compute_pass
+                        .indirect_args
+                        .as_ref()
+                        .is_some_and(|(args, _)| args == name)
+                    || compute_pass
+                        .indirect_count
+                        .as_ref()
+                        .is_some_and(|indirect_count| indirect_count.count == name)
+            }
+            Step::Swap(a, b) => a == name || b == name,
+            Step::Rotate(names) => names.iter().any(|other| other == name),
+            Step::Clear(other) => other == name,
+            Step::Custom(_) => false,
+        })
+    }
+
+    /// Apply the writes deferred by `write_bytes`, failing if a buffer still doesn't exist.
+    /// The other writes are applied anyway, only the failed ones are dropped.
+    fn flush_pending_writes(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for (name, offset, bytes) in std::mem::take(&mut self.pending_writes) {
+            let write = match self.buffers.get(&name) {
+                Some(buffer) => check_write_bounds(&name, buffer, offset, &bytes)
+                    .map(|()| self.render_queue.write_buffer(buffer, offset, &bytes)),
+                None => Err(Error::BufferNotFound(name)),
+            };
+            if result.is_ok() {
+                result = write;
+            }
+        }
+        result
+    }
+
+    /// Refill `target` random number generator states, added with `add_rng_state`, from a new `seed`.
+    #[inline]
+    pub fn try_reseed(&mut self, target: W::Fields, seed: u64) -> Result<()> {
+        let Some(buffer) = &self.buffers.get(target.key()) else {
+            return Err(Error::BufferNotFound(target.key().to_owned()));
+        };
+
+        let count = (buffer.size() / std::mem::size_of::<u32>() as u64) as u32;
+        let seeds = splitmix64_seeds(count, seed);
+
+        self.render_queue
+            .write_buffer(buffer, 0, cast_slice(&seeds));
+
+        Ok(())
+    }
+
+    /// Refill `target` random number generator states, added with `add_rng_state`, from a new `seed`.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn reseed(&mut self, target: W::Fields, seed: u64) {
+        self.try_reseed(target, seed).unwrap()
+    }
+
+    /// Fill `target` with zeros on the GPU, without uploading anything.
+    /// The clear is recorded before the steps of the next run, and after the writes made until then.
+    /// While a run is in flight, there is nothing to record it in, so zeros are uploaded instead.
+    /// Fails if `target` doesn't have the `COPY_DST` usage.
+    pub fn try_clear_buffer(&mut self, target: W::Fields) -> Result<()> {
+        let name = target.key();
+        self.unmap_primary(name);
+        let Some(buffer) = self.buffers.get(name) else {
+            return Err(Error::BufferNotFound(name.to_owned()));
+        };
+        if !buffer.usage().contains(BufferUsages::COPY_DST) {
+            return Err(Error::NotClearable(name.to_owned()));
+        }
+
+        match &mut self.command_encoder {
+            Some(encoder) => encoder.clear_buffer(buffer, 0, None),
+            None => {
+                let zeros = vec![0; buffer.size() as usize];
+                self.render_queue.write_buffer(buffer, 0, &zeros);
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill `target` with zeros on the GPU, before the steps of the next run.
+    /// In case of error, this function will panic.
+    pub fn clear_buffer(&mut self, target: W::Fields) {
+        self.try_clear_buffer(target).unwrap()
+    }
+
+    /// Try Read data from a typed `target`, return a vector of `B: Pod`
+    /// Only fields backed by a staging buffer are accepted.
+    #[inline]
+    pub fn try_read_field<B: AnyBitPattern>(
+        &self,
+        target: impl ReadableField<W::Fields>,
+    ) -> Result<Vec<B>> {
+        self.try_read_vec(target.field())
+    }
+
+    /// Try Read data from a typed `target`, return a vector of `B: Pod`
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn read_field<B: AnyBitPattern>(&self, target: impl ReadableField<W::Fields>) -> Vec<B> {
+        self.try_read_field(target).unwrap()
+    }
+
+    /// Write data to a typed `target`.
+    #[inline]
+    pub fn try_write_field<T: NoUninit>(
+        &mut self,
+        target: impl WritableField<W::Fields>,
+        data: &[T],
+    ) -> Result<()> {
+        self.try_write_slice(target.field(), data)
+    }
+
+    /// Write data to a typed `target`.
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn write_field<T: NoUninit>(&mut self, target: impl WritableField<W::Fields>, data: &[T]) {
+        self.try_write_field(target, data).unwrap()
+    }
+
+    fn submit(&mut self) -> Result<&mut Self> {
+        let command_buffers = self.finish_encoder()?;
+        let (submission, error) =
+            submit_capturing_errors(&self.render_device, &self.render_queue, command_buffers);
+        self.submitted(submission, error);
+        Ok(self)
+    }
+
+    /// Finish the recorded commands, to be submitted, after the ones recorded before the run.
+    fn finish_encoder(&mut self) -> Result<Vec<CommandBuffer>> {
+        let Some(encoder) = self.command_encoder.take() else {
+            return Err(Error::NothingToSubmit);
+        };
+        Ok(self
+            .pre_run_commands
+            .take()
+            .into_iter()
+            .chain(Some(encoder.finish()))
+            .collect())
+    }
+
+    /// Track the submission of the recorded run, and the GPU error it raised, if any.
+    fn submitted(&mut self, submission: SubmissionIndex, error: Option<String>) {
+        self.last_submission = Some(submission);
+        self.frames_in_flight = 0;
+        if error.is_some() {
+            self.last_gpu_error = error;
+        }
+        self.state = WorkerState::Working;
+    }
+
+    /// The compilation errors of this worker's pipelines, as pairs of shader type path,
+    /// followed by `#` and the entry point and by the shader defs for variants, and error message.
+    /// A pipeline is listed until it compiles, e.g. once its shader is fixed and hot reloaded.
+    /// Errors are picked up when pipelines are extracted, in `Update` or by `execute_now`.
+    pub fn pipeline_errors(&self) -> Vec<(String, String)> {
+        let mut errors = self
+            .pipeline_errors
+            .iter()
+            .map(|(key, message)| (key.clone(), message.clone()))
+            .collect::<Vec<_>>();
+        errors.sort();
+        errors
+    }
+
+    /// Take the last error reported by the GPU when recording or submitting this worker's work,
+    /// if any. Validation and out of memory errors are captured. Errors of a submission shared
+    /// with other workers are kept by the [`ComputeScheduler`] instead, see `take_batch_error`.
+    pub fn take_last_gpu_error(&mut self) -> Option<String> {
+        self.last_gpu_error.take()
+    }
+
+    /// Take the last error that made this worker skip a run, if any.
+    /// A run failing to record, e.g. because a pass binds a missing buffer, is logged and skipped
+    /// instead of panicking, and the worker tries again on its next run.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.last_error.take()
+    }
+
+    /// The GPU duration of every pass of the last run, in milliseconds, as pairs of pass label,
+    /// or shader type path for unlabelled passes, and duration.
+    /// Empty unless the worker was built with `enable_timestamps` on an adapter supporting them.
+    /// This blocks until the GPU is done.
+    pub fn try_pass_timings(&self) -> Result<Vec<(String, f64)>> {
+        match &self.timestamps {
+            Some(timestamps) => timestamps.read(&self.render_device, &self.render_queue),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The GPU duration of every pass of the last run, in milliseconds.
+    /// In case of error, this function will panic.
+    pub fn pass_timings(&self) -> Vec<(String, f64)> {
+        self.try_pass_timings().unwrap()
+    }
+
+    #[inline]
+    fn poll(&self) -> bool {
+        // Too many frames behind, wait for this worker's own submission to bound the latency
+        if self
+            .max_frames_in_flight
+            .is_some_and(|max| self.frames_in_flight >= max)
+        {
+            if let Some(submission_index) = &self.last_submission {
+                self.render_device
+                    .wgpu_device()
+                    .poll(wgpu::MaintainBase::WaitForSubmissionIndex(
+                        submission_index.clone(),
+                    ));
+                return true;
+            }
+        }
+
+        let maintain = if self.wait_mode || self.run_mode == RunMode::Immediate {
+            wgpu::MaintainBase::Wait
+        } else {
+            wgpu::MaintainBase::Poll
+        };
+        match self.render_device.wgpu_device().poll(maintain) {
+            wgpu::MaintainResult::SubmissionQueueEmpty => true,
+            wgpu::MaintainResult::Ok => false,
+        }
+    }
+
+    /// Restore every buffer to the data it was created with, without rebuilding the worker.
+    /// Empty buffers are zeroed, scratch buffers and the ones added with `add_existing_buffer`
+    /// are left untouched. Staging buffers are refreshed too, so reading right after
+    /// returns the initial data.
+    /// This blocks until the GPU is done, and fails if the worker is working.
+    pub fn try_reset(&mut self) -> Result<()> {
+        if self.state == WorkerState::Working {
+            return Err(Error::WorkerBusy);
+        }
+
+        // Directly mapped buffers can't be written to while mapped
+        self.unmap_staging_buffers();
+
+        for (name, buffer) in &self.buffers {
+            if !buffer.usage().contains(BufferUsages::COPY_DST)
+                || self.existing_buffers.contains(&buffer.id())
+            {
+                continue;
+            }
+
+            match self.initial_contents.get(name) {
+                Some(contents) if contents.len() as u64 <= buffer.size() => {
+                    self.render_queue.write_buffer(buffer, 0, contents)
+                }
+                _ => self
+                    .render_queue
+                    .write_buffer(buffer, 0, &vec![0; buffer.size() as usize]),
+            }
+        }
+
+        let mut encoder = self
+            .render_device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        for (name, staging_buffer) in &mut self.staging_buffers {
+            let Some(buffer) = self.buffers.get(name) else {
+                return Err(Error::BufferNotFound(name.to_owned()));
+            };
+
+            if staging_buffer.primary {
+                continue;
+            }
+            encoder.copy_buffer_to_buffer(
+                buffer,
+                0,
+                &staging_buffer.buffer,
+                0,
+                staging_buffer.buffer.size(),
+            );
+        }
+        self.render_queue.submit(Some(encoder.finish()));
+
+        let completion = Completion::new(self.staging_buffers.len(), Vec::new());
+        for (_, staging_buffer) in &mut self.staging_buffers {
+            let completion = completion.clone();
+            staging_buffer
+                .buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| match result {
+                    Ok(()) => completion.complete_one(),
+                    Err(err) => completion.fail(err.to_string()),
+                });
+            staging_buffer.mapped = true;
+        }
+        self.render_device
+            .wgpu_device()
+            .poll(wgpu::MaintainBase::Wait);
+
+        match completion.map_error() {
+            Some(message) => Err(Error::MapFailed(message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Restore every buffer to the data it was created with, without rebuilding the worker.
+    /// In case of error, this function will panic.
+    pub fn reset(&mut self) {
+        self.try_reset().unwrap()
+    }
+
+    /// Replace every buffer of the worker while keeping its pipelines and steps,
+    /// e.g. to load a dataset of another size without recompiling the shaders.
+    /// `f` adds the new buffers, and may update the dispatch sizes to match.
+    /// Buffers it doesn't add again are dropped, except query buffers.
+    /// Fails if the worker is working.
+    pub fn try_rebuild_buffers(
+        &mut self,
+        render_device: &RenderDevice,
+        f: impl FnOnce(&mut BufferSet<W>),
+    ) -> Result<()> {
+        if self.state == WorkerState::Working {
+            return Err(Error::WorkerBusy);
+        }
+
+        self.unmap_staging_buffers();
+        let mut old_buffers = std::mem::take(&mut self.buffers);
+        let mut old_staging_buffers = std::mem::take(&mut self.staging_buffers);
+        self.initial_contents.clear();
+        self.binding_sizes.clear();
+
+        // Query buffers only depend on their query set
+        for name in self.query_sets.keys() {
+            if let Some(buffer) = old_buffers.remove(name) {
+                self.buffers.insert(name.clone(), buffer);
+            }
+            if let Some(staging_buffer) = old_staging_buffers.remove(name) {
+                self.staging_buffers.insert(name.clone(), staging_buffer);
+            }
+        }
+
+        f(&mut BufferSet::new(self, render_device));
+        Ok(())
+    }
+
+    /// Replace every buffer of the worker while keeping its pipelines and steps.
+    /// In case of error, this function will panic.
+    pub fn rebuild_buffers(
+        &mut self,
+        render_device: &RenderDevice,
+        f: impl FnOnce(&mut BufferSet<W>),
+    ) {
+        self.try_rebuild_buffers(render_device, f).unwrap()
+    }
+
+    /// Call `f` with the raw bytes of `field` every time a run completes and its new data is available.
+    /// `field` must be readable, i.e. added with `add_staging` or `add_rw_storage`.
+    /// Registering a new callback for the same field replaces the previous one.
+    pub fn on_field_ready(
+        &mut self,
+        field: W::Fields,
+        f: impl FnMut(&[u8]) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.field_callbacks
+            .insert(field.key().to_owned(), Box::new(f));
+        self
+    }
+
+    /// Call `f` with the worker every time a run completes and its staging buffers are mapped,
+    /// so `f` can read its results right away, e.g. with `read_vec`.
+    ///
+    /// `f` isn't called from wgpu's mapping callback, which may run on another thread, but
+    /// on the thread updating the worker once it sees the run completed: in the `PostUpdate`
+    /// of the frame the GPU finished, or in `execute_now`. It must not block for long,
+    /// as it holds up the schedule. Callbacks run in the order they were registered.
+    pub fn on_complete(
+        &mut self,
+        f: impl FnMut(&AppComputeWorker<W>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.complete_callbacks.push(Box::new(f));
+        self
+    }
 
-            staging_buffer.mapped = true;
-        }
+    /// Send `()` on `sender` every time a run completes and its staging buffers are mapped,
+    /// so custom loops can wait on a channel instead of polling [`ready`](Self::ready).
+    /// The device must still be polled, which the worker does every frame.
+    pub fn notify_completion(&mut self, sender: Sender<()>) -> &mut Self {
+        self.completion_senders.push(sender);
         self
     }
 
-    /// Read data from `target` staging buffer, return raw bytes
-    #[inline]
-    pub fn try_read_raw<'a>(
-        &'a self,
-        target: W::Fields,
-    ) -> Result<(impl Deref<Target = [u8]> + 'a)> {
-        let Some(staging_buffer) = &self.staging_buffers.get(&format!("{target:?}")) else {
-            return Err(Error::StagingBufferNotFound(format!("{target:?}")));
-        };
+    /// Bound how many frames the GPU may fall behind when not in wait mode.
+    /// Once the worker has been working for `max` frames, the CPU waits for its
+    /// submission to complete instead of letting the latency grow.
+    /// `None`, the default, never forces a wait.
+    pub fn set_max_frames_in_flight(&mut self, max: Option<u32>) -> &mut Self {
+        self.max_frames_in_flight = max;
+        self
+    }
 
-        let result = staging_buffer.buffer.slice(..).get_mapped_range();
+    ///Set the wait mode of the worker.
+    ///If `wait` is true, the worker will cause the CPU to wait for the GPU to finish before running the next frame.
+    ///The new mode applies from the next run.
+    pub fn set_wait_mode(&mut self, wait: bool) -> &mut Self {
+        self.wait_mode = wait;
+        self
+    }
 
-        Ok(result)
+    /// Whether the worker makes the CPU wait for the GPU to finish.
+    pub fn wait_mode(&self) -> bool {
+        self.wait_mode
     }
 
-    /// Read data from `target` staging buffer, return raw bytes
-    /// Panics on error.
-    #[inline]
-    pub fn read_raw<'a>(&'a self, target: W::Fields) -> (impl Deref<Target = [u8]> + 'a) {
-        self.try_read_raw(target).unwrap()
+    /// The phase of the current run, for coarse progress feedback.
+    /// Steps are recorded all at once, so there's no finer progress within a run.
+    pub fn current_phase(&self) -> Phase {
+        match self.state {
+            WorkerState::Created | WorkerState::Available => Phase::Recording,
+            WorkerState::FinishedWorking => Phase::Ready,
+            WorkerState::Working if self.completion.is_done() => Phase::Ready,
+            WorkerState::Working if self.completion.is_work_done() => Phase::Mapping,
+            WorkerState::Working => Phase::Submitted,
+        }
     }
 
-    /// Try Read data from `target` staging buffer, return a single `B: Pod`
-    #[inline]
-    pub fn try_read<B: AnyBitPattern>(&self, target: W::Fields) -> Result<B> {
-        let result = *from_bytes::<B>(&self.try_read_raw(target)?);
-        Ok(result)
+    /// The state of the worker, e.g. to tell why it doesn't progress.
+    /// A worker stuck in `Created` is usually waiting for its pipelines, see `is_pipeline_ready`.
+    pub fn state(&self) -> &WorkerState {
+        &self.state
     }
 
-    /// Try Read data from `target` staging buffer, return a single `B: Pod`
-    /// In case of error, this function will panic.
-    #[inline]
-    pub fn read<B: AnyBitPattern>(&self, target: W::Fields) -> B {
-        self.try_read(target).unwrap()
+    /// Whether every pipeline of the worker is compiled and extracted, so it can run.
+    /// Otherwise runs are skipped, see `pipeline_errors` for the pipelines that failed.
+    pub fn is_pipeline_ready(&self) -> bool {
+        self.pipelines.values().all(Option::is_some)
     }
 
-    /// Try Read data from `target` staging buffer, return a vector of `B: Pod`
-    #[inline]
-    pub fn try_read_vec<B: AnyBitPattern>(&self, target: W::Fields) -> Result<Vec<B>> {
-        let bytes = self.try_read_raw(target)?;
-        Ok(cast_slice::<u8, B>(&bytes).to_vec())
+    /// A plain data view of the steps of a run, in order, e.g. to display the compute graph.
+    pub fn steps(&self) -> Vec<StepInfo> {
+        self.steps.iter().map(StepInfo::from).collect()
     }
 
-    /// Try Read data from `target` staging buffer, return a vector of `B: Pod`
-    /// In case of error, this function will panic.
-    #[inline]
-    pub fn read_vec<B: AnyBitPattern>(&self, target: W::Fields) -> Vec<B> {
-        self.try_read_vec(target).unwrap()
+    /// The number of steps of a run: passes, swaps and rotations.
+    pub fn total_steps(&self) -> usize {
+        self.steps.len()
     }
 
-    /// Write data to `target` buffer.
-    #[inline]
-    pub fn try_write<T: NoUninit>(&mut self, target: W::Fields, data: &T) -> Result<()> {
-        let Some(buffer) = &self.buffers.get(&format!("{target:?}")) else {
-            return Err(Error::BufferNotFound(format!("{target:?}")));
-        };
+    /// Insert `step` before the step at `index`, or at the end if `index` is the number of steps.
+    /// Passes must use a shader, and shader defs, this worker was built with, and a fixed dispatch size
+    /// without instances. Their fields must match the bindings of the shader, so they fail with
+    /// [`Error::PipelineNotReady`] until it compiled. The buffers of the step must exist, and swapped or rotated ones
+    /// must have the same size and usages, and cleared ones the `COPY_DST` usage.
+    /// Custom steps can't be inserted, their closure is lost.
+    pub fn try_insert_step_at(&mut self, index: usize, step: StepInfo) -> Result<()> {
+        if index > self.steps.len() {
+            return Err(Error::StepOutOfBounds(index));
+        }
 
-        let bytes = bytes_of(data);
+        let names = match &step {
+            StepInfo::Pass {
+                vars, extra_groups, ..
+            } => vars.iter().chain(extra_groups.iter().flatten()).collect(),
+            StepInfo::Swap(a, b) => vec![a, b],
+            StepInfo::Rotate(names) => names.iter().collect(),
+            StepInfo::Clear(name) => vec![name],
+            StepInfo::Custom => vec![],
+        };
+        for name in &names {
+            if !self.buffers.contains_key(*name) && !self.textures.contains_key(*name) {
+                return Err(Error::BufferNotFound(name.to_string()));
+            }
+        }
 
-        self.render_queue.write_buffer(buffer, 0, bytes);
+        let step = match step {
+            StepInfo::Pass {
+                shader,
+                entry_point,
+                shader_defs,
+                label,
+                dispatch_size: Some(dispatch_size),
+                vars,
+                extra_groups,
+                instances: 1,
+                enabled,
+            } => {
+                let pipeline_key = pipeline_key(&shader, entry_point.as_deref(), &shader_defs);
+                if !self.cached_pipeline_ids.contains_key(&pipeline_key) {
+                    return Err(Error::ShaderNotFound(pipeline_key));
+                }
+                check_dispatch_size(&self.render_device, dispatch_size)?;
+                self.check_bindings(&pipeline_key, &vars, &extra_groups)?;
+                Step::ComputePass(Box::new(ComputePass {
+                    dispatch_size,
+                    entry_point,
+                    shader_defs,
+                    label,
+                    extra_groups,
+                    enabled,
+                    ..ComputePass::new(shader, pipeline_key, vars)
+                }))
+            }
+            StepInfo::Pass { .. } | StepInfo::Custom => {
+                return Err(Error::InvalidStep(format!("{step:?}")))
+            }
+            StepInfo::Swap(a, b) => {
+                self.prepare_swap(&[&a, &b])?;
+                Step::Swap(a, b)
+            }
+            StepInfo::Rotate(names) => {
+                self.prepare_swap(&names.iter().collect::<Vec<_>>())?;
+                Step::Rotate(names)
+            }
+            StepInfo::Clear(name) => {
+                // Textures pass the name check above, but only buffers can be cleared
+                let Some(buffer) = self.buffers.get(&name) else {
+                    return Err(Error::NotClearable(name));
+                };
+                if !buffer.usage().contains(BufferUsages::COPY_DST) {
+                    return Err(Error::NotClearable(name));
+                }
+                Step::Clear(name)
+            }
+        };
 
+        self.steps.insert(index, step);
+        self.empty_dispatch_warned.clear();
         Ok(())
     }
 
-    /// Write data to `target` buffer.
+    /// Insert `step` before the step at `index`, or at the end if `index` is the number of steps.
     /// In case of error, this function will panic.
-    #[inline]
-    pub fn write<T: NoUninit>(&mut self, target: W::Fields, data: &T) {
-        self.try_write(target, data).unwrap()
+    pub fn insert_step_at(&mut self, index: usize, step: StepInfo) {
+        self.try_insert_step_at(index, step).unwrap()
     }
 
-    /// Write data to `target` buffer.
-    #[inline]
-    pub fn try_write_slice<T: NoUninit>(&mut self, target: W::Fields, data: &[T]) -> Result<()> {
-        let Some(buffer) = &self.buffers.get(&format!("{target:?}")) else {
-            return Err(Error::BufferNotFound(format!("{target:?}")));
-        };
+    /// Remove the step at `index`, returning it.
+    pub fn try_remove_step(&mut self, index: usize) -> Result<StepInfo> {
+        if index >= self.steps.len() {
+            return Err(Error::StepOutOfBounds(index));
+        }
+
+        let step = self.steps.remove(index);
+        self.empty_dispatch_warned.clear();
+        Ok(StepInfo::from(&step))
+    }
 
-        let bytes = cast_slice(data);
+    /// Remove the step at `index`, returning it.
+    /// In case of error, this function will panic.
+    pub fn remove_step(&mut self, index: usize) -> StepInfo {
+        self.try_remove_step(index).unwrap()
+    }
 
-        self.render_queue.write_buffer(buffer, 0, bytes);
+    /// Move the step at `from` so it ends up at index `to`, shifting the steps in between.
+    pub fn try_move_step(&mut self, from: usize, to: usize) -> Result<()> {
+        for index in [from, to] {
+            if index >= self.steps.len() {
+                return Err(Error::StepOutOfBounds(index));
+            }
+        }
 
+        let step = self.steps.remove(from);
+        self.steps.insert(to, step);
+        self.empty_dispatch_warned.clear();
         Ok(())
     }
 
-    /// Write data to `target` buffer.
+    /// Move the step at `from` so it ends up at index `to`, shifting the steps in between.
     /// In case of error, this function will panic.
-    #[inline]
-    pub fn write_slice<T: NoUninit>(&mut self, target: W::Fields, data: &[T]) {
-        self.try_write_slice(target, data).unwrap()
+    pub fn move_step(&mut self, from: usize, to: usize) {
+        self.try_move_step(from, to).unwrap()
     }
 
-    fn submit(&mut self) -> &mut Self {
-        let encoder = self.command_encoder.take().unwrap();
-        self.render_queue.submit(Some(encoder.finish()));
-        self.state = WorkerState::Working;
-        self
+    /// Check the buffers behind `names` can be swapped, as checked when building the worker,
+    /// and give the directly mapped ones a staging buffer of their own, as it is done then.
+    fn prepare_swap(&mut self, names: &[&String]) -> Result<()> {
+        check_swappable(&self.buffers, names)?;
+
+        for name in names {
+            let Some(staging) = self.staging_buffers.get_mut(*name) else {
+                continue;
+            };
+            if !staging.primary {
+                continue;
+            }
+            // Its mapping may be pending
+            if self.state == WorkerState::Working {
+                return Err(Error::WorkerBusy);
+            }
+            detach_primary_staging(&self.render_device, name, staging);
+        }
+        Ok(())
     }
 
-    #[inline]
-    fn poll(&self) -> bool {
-        let maintain = if self.wait_mode || self.run_mode == RunMode::Immediate {
-            wgpu::MaintainBase::Wait
+    /// Check `vars` and `extra_groups` match the bind group layouts of the pipeline
+    /// `pipeline_key`, by creating their bind groups.
+    fn check_bindings(
+        &self,
+        pipeline_key: &str,
+        vars: &[String],
+        extra_groups: &[Vec<String>],
+    ) -> Result<()> {
+        let Some(Some(pipeline)) = self.pipelines.get(pipeline_key) else {
+            return Err(Error::PipelineNotReady);
+        };
+        let device = self.render_device.wgpu_device();
+
+        if vars.is_empty() {
+            // Asking for the layout of a shader without bindings is a wgpu error
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+            pipeline.get_bind_group_layout(0);
+            if block_on(device.pop_error_scope()).is_none() {
+                return Err(Error::BindingMismatch(
+                    pipeline_key.to_owned(),
+                    "the shader has bindings in group 0 but the pass has no field".to_owned(),
+                ));
+            }
+        }
+
+        let entries = self.bind_group_entries(vars, None)?;
+        let mut extra_entries = vec![];
+        for vars in extra_groups {
+            extra_entries.push(self.bind_group_entries(vars, None)?);
+        }
+        let layout = if entries.is_empty() {
+            None
         } else {
-            wgpu::MaintainBase::Poll
+            let Some(layout) = self.bind_group_layouts.get(pipeline_key) else {
+                return Err(Error::PipelineNotReady);
+            };
+            Some(layout)
         };
-        match self.render_device.wgpu_device().poll(maintain) {
-            wgpu::MaintainResult::SubmissionQueueEmpty => true,
-            wgpu::MaintainResult::Ok => false,
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        if let Some(layout) = layout {
+            self.render_device.create_bind_group(None, layout, &entries);
+        }
+        // Only group 0 may have an explicit layout, the others are always the pipeline's
+        for (index, entries) in extra_entries.iter().enumerate() {
+            if entries.is_empty() {
+                continue;
+            }
+            let layout = pipeline.get_bind_group_layout(index as u32 + 1).into();
+            self.render_device.create_bind_group(None, &layout, entries);
+        }
+        match block_on(device.pop_error_scope()) {
+            Some(err) => Err(Error::BindingMismatch(
+                pipeline_key.to_owned(),
+                err.to_string(),
+            )),
+            None => Ok(()),
         }
     }
 
@@ -479,96 +2984,330 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
         self.state == WorkerState::FinishedWorking
     }
 
-    /// Tell the worker to execute the compute shader at the end of the current frame
+    /// Tell the worker to execute the compute shader at the end of the current frame.
+    /// Fails with [`Error::WrongRunMode`] for immediate workers, use `execute_now` instead.
     #[inline]
-    pub fn execute(&mut self) {
+    pub fn try_execute(&mut self) -> Result<()> {
         match self.run_mode {
-            RunMode::Continuous => {}
+            RunMode::Continuous | RunMode::EveryNFrames(_) => {}
             RunMode::OneShot(_) => self.run_mode = RunMode::OneShot(true),
-            RunMode::Immediate => {
-                panic!("Immediate mode is not supported in execute(), please use execute_now() instead");
-            }
+            RunMode::Immediate => return Err(Error::WrongRunMode(self.run_mode)),
         }
+        Ok(())
     }
 
-    ///Execute the compute shader immediately and wait for the result. This will return false if the worker is not ready to execute, e.g the pipeline is not ready. This will only happen before the first time the ExtractSchedule is run.
-    pub fn execute_now(&mut self, pipeline_cache: &AppPipelineCache) -> bool {
+    /// Tell the worker to execute the compute shader at the end of the current frame
+    /// In case of error, this function will panic.
+    #[inline]
+    pub fn execute(&mut self) {
+        self.try_execute().unwrap()
+    }
+
+    /// Execute the compute shader immediately and wait for the result. This will return false if the worker is not ready to execute, e.g the pipeline is not ready. This will only happen before the first time the ExtractSchedule is run.
+    /// Fails with [`Error::WrongRunMode`] unless the worker is immediate, use `execute` instead.
+    pub fn try_execute_now(&mut self, pipeline_cache: &AppPipelineCache) -> Result<bool> {
         match self.run_mode {
-            RunMode::Continuous | RunMode::OneShot(_) => {
-                panic!("Continuous and OneShot modes are not supported in execute_now(), please use execute() instead");
+            RunMode::Continuous | RunMode::OneShot(_) | RunMode::EveryNFrames(_) => {
+                Err(Error::WrongRunMode(self.run_mode))
             }
             RunMode::Immediate => {
-                self.extract_pipelines_aux(&pipeline_cache);
+                self.extract_pipelines_aux(pipeline_cache);
                 self.unmap_all_aux();
                 self.poll();
-                self.run_immediate()
+                Ok(self.run_immediate())
             }
         }
     }
 
-    #[inline]
-    fn ready_to_execute(&self) -> bool {
-        (self.state != WorkerState::Working) && (self.run_mode != RunMode::OneShot(false))
+    /// Execute the compute shader immediately and wait for the result. This will return false if the worker is not ready to execute, e.g the pipeline is not ready. This will only happen before the first time the ExtractSchedule is run.
+    /// In case of error, this function will panic.
+    pub fn execute_now(&mut self, pipeline_cache: &AppPipelineCache) -> bool {
+        self.try_execute_now(pipeline_cache).unwrap()
+    }
+
+    /// Record and submit the compute work right away, outside of the Bevy schedule.
+    /// The returned future resolves once the GPU is done and the staging buffers can be read,
+    /// so it can be awaited from an async task, e.g. spawned on the `AsyncComputeTaskPool`.
+    /// It doesn't rely on the app updating meanwhile: while pending, a thread waits for the GPU
+    /// and wakes the task, which isn't polled again before.
+    ///
+    /// Pipelines must have been extracted by then, else the future resolves
+    /// to [`Error::PipelineNotReady`]. It also fails if the worker is already working.
+    pub fn execute_async(&mut self) -> impl Future<Output = Result<()>> {
+        match self.try_submit_now() {
+            Ok(()) => CompletionFuture::Pending {
+                render_device: self.render_device.clone(),
+                completion: self.completion.clone(),
+                waiting: false,
+            },
+            Err(err) => CompletionFuture::Failed(Some(err)),
+        }
     }
 
-    pub(crate) fn run(mut worker: ResMut<Self>) {
-        worker.run_aux();
+    fn try_submit_now(&mut self) -> Result<()> {
+        if self.state == WorkerState::Working {
+            return Err(Error::WorkerBusy);
+        }
+
+        self.unmap_staging_buffers();
+        self.capturing_errors(Self::try_record_run)?;
+        self.submit()?;
+        self.map_staging_buffers();
+
+        self.command_encoder = Some(
+            self.render_device
+                .create_command_encoder(&CommandEncoderDescriptor { label: None }),
+        );
+        Ok(())
     }
-    fn run_immediate(&mut self) -> bool {
+
+    /// Record every step in the command encoder, grouped under the worker's name
+    /// so GPU captures show them together.
+    fn record_steps(&mut self) -> Result<()> {
+        // Nothing may be recorded, nor submitted early, before every pipeline is ready
+        if self.pipeline_pending() {
+            return Err(Error::PipelineNotReady);
+        }
+
+        self.flush_pending_writes()?;
+
+        let Some(encoder) = &mut self.command_encoder else {
+            return Err(Error::EncoderIsNone);
+        };
+        encoder.push_debug_group(std::any::type_name::<W>());
+
+        if let Some(timestamps) = &mut self.timestamps {
+            let passes = self
+                .steps
+                .iter()
+                .filter(|step| matches!(step, Step::ComputePass(_)))
+                .count();
+            timestamps.begin_run(&self.render_device, passes as u32);
+        }
+
         // Workaround for interior mutability
+        let mut result = Ok(());
+        let mut passes = 0;
         for i in 0..self.steps.len() {
-            let result = match self.steps[i] {
+            result = match self.steps[i] {
                 Step::ComputePass(_) => self.dispatch(i),
                 Step::Swap(_, _) => self.swap(i),
+                Step::Rotate(_) => self.rotate(i),
+                Step::Clear(_) => self.clear(i),
+                Step::Custom(_) => self.custom(i),
             };
 
-            if let Err(err) = result {
-                match err {
-                    Error::PipelineNotReady => return false,
-                    _ => panic!("{:?}", err),
+            if result.is_err() {
+                break;
+            }
+
+            if matches!(&self.steps[i], Step::ComputePass(compute_pass) if compute_pass.enabled) {
+                passes += 1;
+                let last_step = i + 1 == self.steps.len();
+                if self.submit_every.is_some_and(|n| passes.is_multiple_of(n)) && !last_step {
+                    self.submit_chunk();
                 }
             }
         }
 
-        self.read_staging_buffers().unwrap();
-        self.submit();
-        self.map_staging_buffers();
+        // The group must be closed even on error, the encoder is reused on the next run
+        if let Some(encoder) = &mut self.command_encoder {
+            encoder.pop_debug_group();
+        }
+        result
+    }
+
+    #[inline]
+    fn pipeline_pending(&self) -> bool {
+        self.steps.iter().any(|step| match step {
+            Step::ComputePass(compute_pass) => self
+                .pipelines
+                .get(&compute_pass.pipeline_key)
+                .is_some_and(Option::is_none),
+            _ => false,
+        })
+    }
+
+    /// Submit the steps recorded so far, and go on recording in a new encoder.
+    /// They run even if recording the rest of the run fails, so the buffers they swapped
+    /// are no longer put back then.
+    fn submit_chunk(&mut self) {
+        let Some(mut encoder) = self.command_encoder.take() else {
+            return;
+        };
+        // Debug groups can't span several command buffers
+        encoder.pop_debug_group();
+        let command_buffers = self
+            .pre_run_commands
+            .take()
+            .into_iter()
+            .chain(Some(encoder.finish()));
+        let (_, error) =
+            submit_capturing_errors(&self.render_device, &self.render_queue, command_buffers);
+        if error.is_some() {
+            self.last_gpu_error = error;
+        }
+        self.buffers_before_run = self.buffers.clone();
+
+        let mut encoder = self
+            .render_device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        encoder.push_debug_group(std::any::type_name::<W>());
+        self.command_encoder = Some(encoder);
+    }
+
+    #[inline]
+    fn ready_to_execute(&self) -> bool {
+        let frame_matches = match self.run_mode {
+            RunMode::EveryNFrames(n) => self.frame.is_multiple_of(n.max(1)),
+            _ => true,
+        };
+        (self.state != WorkerState::Working)
+            && (self.run_mode != RunMode::OneShot(false))
+            && frame_matches
+    }
+
+    pub(crate) fn record(mut worker: ResMut<Self>, mut scheduler: ResMut<ComputeScheduler>) {
+        if let Some(command_buffers) = worker.record_aux() {
+            scheduler.push(command_buffers);
+        }
+    }
+
+    pub(crate) fn run(
+        mut worker: ResMut<Self>,
+        scheduler: Res<ComputeScheduler>,
+        mut finished: EventWriter<WorkerFinished<W>>,
+    ) {
+        if worker.run_aux(&scheduler) {
+            finished.send(WorkerFinished::default());
+        }
+    }
+
+    /// Record a run along with the copies to the staging buffers, returning whether it was.
+    /// Errors other than pipelines not being ready are logged and kept for `take_error`,
+    /// and what was recorded of the run is dropped.
+    fn record_run(&mut self) -> bool {
+        match self.try_record_run() {
+            Ok(()) => true,
+            Err(Error::PipelineNotReady) => false,
+            Err(err) => {
+                error!("Skipping the run of {}: {err}", std::any::type_name::<W>());
+                self.last_error = Some(err);
+                false
+            }
+        }
+    }
+
+    /// Record a run along with the copies to the staging buffers. On error, the run is
+    /// dropped and the buffers swapped so far are put back, but what was recorded
+    /// before the run, and the chunks of it already submitted, are still submitted.
+    fn try_record_run(&mut self) -> Result<()> {
+        if self.pipeline_pending() {
+            return Err(Error::PipelineNotReady);
+        }
+
+        let Some(encoder) = self.command_encoder.take() else {
+            return Err(Error::EncoderIsNone);
+        };
+        self.pre_run_commands = Some(encoder.finish());
+        self.command_encoder = Some(
+            self.render_device
+                .create_command_encoder(&CommandEncoderDescriptor { label: None }),
+        );
 
-        if self.poll() {
+        self.buffers_before_run = self.buffers.clone();
+        let result = self
+            .record_steps()
+            .and_then(|()| self.read_staging_buffers().map(|_| ()));
+        let buffers_before_run = std::mem::take(&mut self.buffers_before_run);
+        if result.is_err() {
+            self.buffers = buffers_before_run;
+            self.render_queue.submit(self.pre_run_commands.take());
             self.command_encoder = Some(
                 self.render_device
                     .create_command_encoder(&CommandEncoderDescriptor { label: None }),
             );
         }
+        result
+    }
+
+    fn run_immediate(&mut self) -> bool {
+        if !self.capturing_errors(Self::record_run) {
+            return false;
+        }
+
+        self.submit().unwrap();
+        self.map_staging_buffers();
+
+        // Immediate mode always waits for the GPU, so the run is complete even if
+        // other work keeps the queue busy, and the worker can run again right away
+        self.poll();
+        self.state = WorkerState::FinishedWorking;
+        self.command_encoder = Some(
+            self.render_device
+                .create_command_encoder(&CommandEncoderDescriptor { label: None }),
+        );
+        self.notify_run_complete();
         true
     }
-    fn run_aux(&mut self) {
+    /// Record the run of this frame if the worker should run, returning the commands
+    /// for the [`ComputeScheduler`] to submit.
+    fn record_aux(&mut self) -> Option<Vec<CommandBuffer>> {
         if self.ready() {
             self.state = WorkerState::Available;
         }
 
-        if self.ready_to_execute() {
-            // Workaround for interior mutability
-            for i in 0..self.steps.len() {
-                let result = match self.steps[i] {
-                    Step::ComputePass(_) => self.dispatch(i),
-                    Step::Swap(_, _) => self.swap(i),
-                };
+        if !self.ready_to_execute() {
+            return None;
+        }
 
-                if let Err(err) = result {
-                    match err {
-                        Error::PipelineNotReady => return,
-                        _ => panic!("{:?}", err),
-                    }
-                }
+        // Errors of this run are only attributed to this worker while it is recorded,
+        // the scheduler submits every worker's run at once
+        let command_buffers = self.capturing_errors(|worker| {
+            worker
+                .record_run()
+                .then(|| worker.finish_encoder().unwrap())
+        })?;
+
+        self.batched = true;
+        Some(command_buffers)
+    }
+
+    /// Run `f`, keeping the validation errors it raises, e.g. by recording a pass with
+    /// mismatched bindings, as the last GPU error of this worker.
+    fn capturing_errors<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let render_device = self.render_device.clone();
+        let device = render_device.wgpu_device();
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = f(self);
+        if let Some(err) = block_on(device.pop_error_scope()) {
+            self.last_gpu_error = Some(err.to_string());
+        }
+        result
+    }
+
+    /// Go on with the run of this frame once the scheduler submitted it,
+    /// returning whether a run completed.
+    fn run_aux(&mut self, scheduler: &ComputeScheduler) -> bool {
+        if std::mem::take(&mut self.batched) {
+            if let Some((submission, error)) = &scheduler.last_batch {
+                self.submitted(submission.clone(), error.clone());
+                self.map_staging_buffers();
             }
+        }
 
-            self.read_staging_buffers().unwrap();
-            self.submit();
-            self.map_staging_buffers();
+        if self.state == WorkerState::Working {
+            self.frames_in_flight += 1;
         }
+        self.frame = self.frame.wrapping_add(1);
 
-        if self.run_mode != RunMode::OneShot(false) && self.poll() {
+        // Throttled workers are only ready on the frames their work completes
+        let idle =
+            matches!(self.run_mode, RunMode::EveryNFrames(_)) && self.state != WorkerState::Working;
+
+        let mut finished_run = false;
+        if self.run_mode != RunMode::OneShot(false) && !idle && self.poll() {
+            finished_run = self.state == WorkerState::Working;
             self.state = WorkerState::FinishedWorking;
             self.command_encoder = Some(
                 self.render_device
@@ -576,27 +3315,128 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
             );
 
             match self.run_mode {
-                RunMode::Continuous | RunMode::Immediate => {}
+                RunMode::Continuous | RunMode::Immediate | RunMode::EveryNFrames(_) => {}
                 RunMode::OneShot(_) => self.run_mode = RunMode::OneShot(false),
             };
+
+            if finished_run {
+                self.notify_run_complete();
+            }
+        }
+
+        // Work submitted with `execute_async` completes outside of the schedule
+        if self.state == WorkerState::Working && self.completion.is_done() {
+            self.state = WorkerState::FinishedWorking;
+            if self.command_encoder.is_none() {
+                self.command_encoder = Some(
+                    self.render_device
+                        .create_command_encoder(&CommandEncoderDescriptor { label: None }),
+                );
+            }
+            self.notify_run_complete();
+            finished_run = true;
+        }
+
+        finished_run
+    }
+
+    /// Call the callbacks registered with `on_field_ready` with the new data of their field,
+    /// then the ones registered with `on_complete`.
+    fn notify_run_complete(&mut self) {
+        for (name, callback) in &mut self.field_callbacks {
+            let staging_buffer = self.staging_buffers.get(name);
+            if let Some(staging_buffer) = staging_buffer.filter(|staging| staging.mapped) {
+                callback(&staging_buffer.buffer.slice(..).get_mapped_range());
+            } else if let Some(buffer) = self.buffers.get(name) {
+                if buffer.usage().contains(BufferUsages::COPY_SRC) {
+                    // Unlike staging fields, there is no new data without a successful read
+                    if let Ok(bytes) =
+                        read_buffer_blocking(&self.render_device, &self.render_queue, buffer)
+                    {
+                        callback(&bytes);
+                    }
+                }
+            }
         }
+
+        // The callbacks borrow the whole worker, so they're set aside meanwhile
+        let mut complete_callbacks = std::mem::take(&mut self.complete_callbacks);
+        for callback in &mut complete_callbacks {
+            callback(self);
+        }
+        self.complete_callbacks = complete_callbacks;
     }
 
-    pub(crate) fn unmap_all(mut worker: ResMut<Self>) {
+    pub(crate) fn auto_unmap(mut worker: ResMut<Self>) {
         worker.unmap_all_aux();
     }
 
     fn unmap_all_aux(&mut self) {
         if self.ready_to_execute() || self.run_mode == RunMode::Immediate {
-            for (_, staging_buffer) in &mut self.staging_buffers {
-                if staging_buffer.mapped {
-                    staging_buffer.buffer.unmap();
-                    staging_buffer.mapped = false;
-                }
+            self.unmap_staging_buffers();
+        }
+    }
+
+    /// Unmap the staging buffers, so the next run can copy its results into them.
+    /// Only needed for workers built with `manual_unmap()`: call it once you're done
+    /// reading, before the worker runs in `PostUpdate`. Does nothing while the worker is working.
+    pub fn unmap_all(&mut self) {
+        if self.state != WorkerState::Working {
+            self.unmap_staging_buffers();
+        }
+    }
+
+    fn unmap_staging_buffers(&mut self) {
+        for (_, staging_buffer) in &mut self.staging_buffers {
+            if staging_buffer.mapped {
+                staging_buffer.buffer.unmap();
+                staging_buffer.mapped = false;
+            }
+        }
+    }
+
+    /// Unmap `name` if it is mapped directly, so it can be written to.
+    /// Reading it fails until the next run maps it again.
+    fn unmap_primary(&mut self, name: &str) {
+        if let Some(staging_buffer) = self.staging_buffers.get_mut(name) {
+            if staging_buffer.primary && staging_buffer.mapped {
+                staging_buffer.buffer.unmap();
+                staging_buffer.mapped = false;
             }
         }
     }
 
+    /// The content of `name` if it is mapped directly, since the GPU can't copy from it then.
+    fn mapped_primary(&self, name: &str) -> Option<BufferView<'_>> {
+        let staging_buffer = self.staging_buffers.get(name)?;
+        let map_pending = self.state == WorkerState::Working && !self.completion.is_done();
+        (staging_buffer.primary && staging_buffer.mapped && !map_pending)
+            .then(|| staging_buffer.buffer.slice(..).get_mapped_range())
+    }
+
+    /// Queue the pipelines requested by `set_shader_defs` in the render world,
+    /// and switch the worker to their new ids.
+    pub(crate) fn queue_pending_pipelines(
+        pipeline_cache: Res<PipelineCache>,
+        mut main_world: ResMut<MainWorld>,
+    ) {
+        let Some(mut worker) = main_world.get_resource_mut::<Self>() else {
+            return;
+        };
+        if worker.pending_pipelines.is_empty() {
+            return;
+        }
+
+        for (pipeline_key, descriptor) in std::mem::take(&mut worker.pending_pipelines) {
+            let defs = shader_defs_text(&descriptor.shader_defs);
+            let cached_id = pipeline_cache.queue_compute_pipeline(descriptor);
+            worker
+                .pipeline_ids_by_defs
+                .insert((pipeline_key.clone(), defs), cached_id);
+            worker.cached_pipeline_ids.insert(pipeline_key, cached_id);
+        }
+    }
+
     pub(crate) fn extract_pipelines(
         mut worker: ResMut<Self>,
         pipeline_cache: Res<AppPipelineCache>,
@@ -605,21 +3445,66 @@ impl<W: ComputeWorker> AppComputeWorker<W> {
     }
 
     fn extract_pipelines_aux(&mut self, pipeline_cache: &AppPipelineCache) {
-        for (type_path, cached_id) in &self.cached_pipeline_ids.clone() {
-            let Some(pipeline) = self.pipelines.get(type_path) else {
+        for (pipeline_key, cached_id) in &self.cached_pipeline_ids.clone() {
+            let Some(current) = self.pipelines.get(pipeline_key) else {
                 continue;
             };
-
-            if pipeline.is_some() {
+            // Its id still is the one of the previous shader defs
+            let pending = self
+                .pending_pipelines
+                .iter()
+                .any(|(key, _)| key == pipeline_key);
+            if pending {
                 continue;
+            }
+
+            // Pipelines are recompiled under the same id when their shader is modified,
+            // e.g. hot reloaded by the asset server, so a ready pipeline may be replaced
+            let pipeline = match pipeline_cache.get_compute_pipeline_result(*cached_id) {
+                Ok(Some(pipeline)) => pipeline,
+                Ok(None) => continue,
+                Err(message) => {
+                    // A failed reload keeps the previous pipeline running
+                    let new_error = self
+                        .pipeline_errors
+                        .insert(pipeline_key.clone(), message.clone())
+                        .is_none();
+                    if current.is_some() && new_error {
+                        error!("Reloading the pipeline of {pipeline_key} failed, the previous one keeps running: {message}");
+                    }
+                    continue;
+                }
             };
+            if current
+                .as_ref()
+                .is_some_and(|current| current.id() == pipeline.id())
+            {
+                continue;
+            }
+            let pipeline = pipeline.clone();
+            self.pipeline_errors.remove(pipeline_key);
+
+            // Asking for the layout of a shader without bindings is a wgpu error
+            let has_bindings = self.steps.iter().any(|step| match step {
+                Step::ComputePass(compute_pass) => {
+                    &compute_pass.pipeline_key == pipeline_key && !compute_pass.vars.is_empty()
+                }
+                _ => false,
+            });
 
-            let cached_id = *cached_id;
+            let has_explicit_layout = self.bind_group_layouts.contains_key(pipeline_key)
+                && !self.reflected_layouts.contains(pipeline_key);
 
-            self.pipelines.insert(
-                type_path.clone(),
-                pipeline_cache.get_compute_pipeline(cached_id).cloned(),
-            );
+            // Variants of a shader may reflect different layouts, each pass binds its own
+            if has_bindings && !has_explicit_layout {
+                self.bind_group_layouts.insert(
+                    pipeline_key.clone(),
+                    pipeline.get_bind_group_layout(0).into(),
+                );
+                self.reflected_layouts.insert(pipeline_key.clone());
+            }
+
+            self.pipelines.insert(pipeline_key.clone(), Some(pipeline));
         }
     }
 }
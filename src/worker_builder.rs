@@ -2,8 +2,9 @@ use std::{
     borrow::Cow,
     fs::File,
     hash::{DefaultHasher, Hash, Hasher},
-    io::prelude::Read,
+    io::{prelude::Read, ErrorKind},
     marker::PhantomData,
+    sync::Arc,
 };
 
 use bevy::{
@@ -11,47 +12,104 @@ use bevy::{
     prelude::{App, AssetServer},
     render::{
         render_resource::{
-            encase::{private::WriteInto, StorageBuffer, UniformBuffer},
-            Buffer, CachedComputePipelineId, ComputePipelineDescriptor, PipelineCache, Shader,
-            ShaderRef, ShaderType,
+            encase::private::WriteInto, BindGroupLayout, Buffer, BufferId, CachedComputePipelineId,
+            ComputePipelineDescriptor, PipelineCache, Shader, ShaderDefVal, ShaderRef, ShaderSize,
+            ShaderType, Source, Texture, TextureDescriptor, TextureUsages, TextureViewDescriptor,
         },
-        renderer::RenderDevice,
+        renderer::{RenderDevice, RenderQueue},
+        texture::Image,
         RenderApp,
     },
-    utils::HashMap,
+    utils::{HashMap, HashSet},
+};
+use bytemuck::cast_slice;
+use naga::{Module, ShaderStage};
+use wgpu::{
+    util::{BufferInitDescriptor, TextureDataOrder},
+    BufferDescriptor, BufferUsages, CommandEncoder, Features, QuerySet, COPY_BUFFER_ALIGNMENT,
+    QUERY_SIZE,
 };
-use std::fmt::Debug;
-use wgpu::{util::BufferInitDescriptor, BufferDescriptor, BufferUsages};
 
 use crate::{
-    traits::{ComputeShader, ComputeWorker},
-    worker::{AppComputeWorker, ComputePass, RunMode, StagingBuffer, Step},
+    encoding::{encode_storage, encode_uniform},
+    error::{Error, Result},
+    fields::{ComputeFields, RwStorageField, StagingField, StorageField, UniformField},
+    indirect::IndirectCount,
+    pipeline_cache::AppPipelineCache,
+    rng::splitmix64_seeds,
+    traits::{workgroups_for, ComputeShader, ComputeWorker},
+    worker::{
+        check_dispatch_size, check_swappable, pipeline_key, shader_defs_text, AppComputeWorker,
+        BoundTexture, ComputePass, CustomStep, DynamicSize, Instances, PipelineSource, RunMode,
+        StagingBuffer, Step,
+    },
+    workgroup_storage::{declared_workgroup_size, parse_with_defs, workgroup_storage_size},
 };
 
+/// The size of the `[x, y, z]` workgroup counts of an indirect dispatch.
+const INDIRECT_ARGS_SIZE: u64 = 3 * std::mem::size_of::<u32>() as u64;
+
+/// The names of `fields`, as stored in steps.
+fn keys<E: ComputeFields>(fields: &[E]) -> Vec<String> {
+    fields.iter().map(|a| a.key().to_owned()).collect()
+}
+
 /// A builder struct to build [`AppComputeWorker<W>`]
 /// from your structs implementing [`ComputeWorker`]
-pub struct AppComputeWorkerBuilder<'a, W: ComputeWorker, E: Debug + Copy> {
+pub struct AppComputeWorkerBuilder<'a, W: ComputeWorker, E: ComputeFields> {
     pub(crate) app: &'a mut App,
     pub(crate) cached_pipeline_ids: HashMap<String, CachedComputePipelineId>,
+    pub(crate) bind_group_layouts: HashMap<String, BindGroupLayout>,
     pub(crate) buffers: HashMap<String, Buffer>,
+    pub(crate) textures: HashMap<String, BoundTexture>,
+    pub(crate) initial_contents: HashMap<String, Vec<u8>>,
+    pub(crate) binding_sizes: HashMap<String, u64>,
     pub(crate) staging_buffers: HashMap<String, StagingBuffer>,
+    pub(crate) query_sets: HashMap<String, (Arc<QuerySet>, u32)>,
+    pub(crate) pipeline_sources: HashMap<String, PipelineSource>,
+    /// The `@workgroup_size` of each pipeline, when its shader can be parsed on its own.
+    pub(crate) workgroup_sizes: HashMap<String, [u32; 3]>,
+    scratch_buffers: HashMap<u64, Buffer>,
     pub(crate) steps: Vec<Step>,
     pub(crate) run_mode: RunMode,
     pub(crate) wait_mode: bool,
+    pub(crate) manual_unmap: bool,
+    pub(crate) initially_ready: bool,
+    pub(crate) submit_every: Option<u32>,
+    pub(crate) timestamps: bool,
+    /// The buffers cleared on the GPU before the worker's first run, by field.
+    /// A field replaced since holds another buffer, which isn't cleared.
+    pub(crate) zeroed: HashMap<String, BufferId>,
+    /// The buffers added with `add_existing_buffer`, which the worker doesn't own.
+    pub(crate) existing_buffers: HashSet<BufferId>,
     _phantom: PhantomData<(W, E)>,
 }
 
-impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
+impl<'a, W: ComputeWorker, E: ComputeFields> AppComputeWorkerBuilder<'a, W, E> {
     /// Create a new builder.
     pub fn new(app: &'a mut App) -> Self {
         Self {
             app,
             cached_pipeline_ids: HashMap::default(),
+            bind_group_layouts: HashMap::default(),
             buffers: HashMap::default(),
+            textures: HashMap::default(),
+            initial_contents: HashMap::default(),
+            binding_sizes: HashMap::default(),
             staging_buffers: HashMap::default(),
+            query_sets: HashMap::default(),
+            pipeline_sources: HashMap::default(),
+            workgroup_sizes: HashMap::default(),
+            scratch_buffers: HashMap::default(),
             steps: vec![],
             run_mode: RunMode::Continuous,
             wait_mode: true,
+            manual_unmap: false,
+            initially_ready: false,
+            submit_every: None,
+            timestamps: false,
+            zeroed: HashMap::default(),
+            existing_buffers: HashSet::default(),
             _phantom: PhantomData,
         }
     }
@@ -65,94 +123,357 @@ impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
         self
     }
 
+    /// Don't unmap the staging buffers automatically at the start of `PostUpdate`.
+    /// Mapped data then stays readable for the whole frame, but you must call
+    /// [`AppComputeWorker::unmap_all`] yourself before the worker runs again.
+    pub fn manual_unmap(&mut self) -> &mut Self {
+        self.manual_unmap = true;
+        self
+    }
+
+    /// Make the worker ready from the start, before it ever ran.
+    /// `ready()` will return true right after it is built, and reading staging buffers
+    /// will return the data they were created with.
+    pub fn initially_ready(&mut self) -> &mut Self {
+        self.initially_ready = true;
+        self
+    }
+
+    /// Submit the recorded work every `n` passes instead of once per run, so the GPU
+    /// starts on long pipelines earlier and no command buffer gets huge.
+    /// Staging buffers are still read at the end of the run. If recording fails midway,
+    /// the chunks already submitted still run and keep the buffers they swapped.
+    pub fn submit_per_n_passes(&mut self, n: u32) -> &mut Self {
+        self.submit_every = Some(n.max(1));
+        self
+    }
+
+    /// Time every compute pass on the GPU, see [`AppComputeWorker::pass_timings`].
+    /// Does nothing if the adapter lacks the `TIMESTAMP_QUERY` feature.
+    pub fn enable_timestamps(&mut self) -> &mut Self {
+        self.timestamps = true;
+        self
+    }
+
     /// Add a new uniform buffer to the worker, and fill it with `uniform`.
+    /// Fails if `uniform` can't be encoded.
+    pub fn try_add_uniform<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        uniform: &T,
+    ) -> Result<&mut Self> {
+        self.try_add_uniform_with_usage(name, uniform, BufferUsages::empty())
+    }
+
+    /// Add a new uniform buffer to the worker, and fill it with `uniform`.
+    /// In case of error, this function will panic.
     pub fn add_uniform<T: ShaderType + WriteInto>(&mut self, name: E, uniform: &T) -> &mut Self {
-        T::assert_uniform_compat();
-        let mut buffer = UniformBuffer::new(Vec::new());
-        buffer.write::<T>(uniform).unwrap();
+        self.try_add_uniform(name, uniform).unwrap()
+    }
 
-        let render_device = self.app.world.resource::<RenderDevice>();
+    /// Like [`try_add_uniform`](Self::try_add_uniform), with `extra_usage` on top of
+    /// the default usages, e.g. to also use the buffer outside of the worker.
+    /// Fails if `uniform` can't be encoded, or if the usages can't be combined.
+    pub fn try_add_uniform_with_usage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        uniform: &T,
+        extra_usage: BufferUsages,
+    ) -> Result<&mut Self> {
+        let contents = encode_uniform(name.key(), uniform)?;
+        self.add_buffer(
+            name,
+            contents,
+            BufferUsages::COPY_DST | BufferUsages::UNIFORM | extra_usage,
+        )
+    }
 
-        self.buffers.insert(
-            format!("{name:?}"),
-            render_device.create_buffer_with_data(&BufferInitDescriptor {
-                label: Some(&format!("{name:?}")),
-                contents: buffer.as_ref(),
-                usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
-            }),
-        );
-        self
+    /// Like [`add_uniform`](Self::add_uniform), with `extra_usage` on top of the default usages.
+    /// In case of error, this function will panic.
+    pub fn add_uniform_with_usage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        uniform: &T,
+        extra_usage: BufferUsages,
+    ) -> &mut Self {
+        self.try_add_uniform_with_usage(name, uniform, extra_usage)
+            .unwrap()
     }
 
     /// Add a new storage buffer to the worker, and fill it with `storage`. It will be read only.
+    /// Fails if `storage` can't be encoded.
+    pub fn try_add_storage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        storage: &T,
+    ) -> Result<&mut Self> {
+        self.try_add_storage_with_usage(name, storage, BufferUsages::empty())
+    }
+
+    /// Add a new storage buffer to the worker, and fill it with `storage`. It will be read only.
+    /// In case of error, this function will panic.
     pub fn add_storage<T: ShaderType + WriteInto>(&mut self, name: E, storage: &T) -> &mut Self {
-        let mut buffer = StorageBuffer::new(Vec::new());
-        buffer.write::<T>(storage).unwrap();
+        self.try_add_storage(name, storage).unwrap()
+    }
 
-        let render_device = self.app.world.resource::<RenderDevice>();
+    /// Like [`try_add_storage`](Self::try_add_storage), with `extra_usage` on top of
+    /// the default usages, e.g. `VERTEX` or `INDIRECT` to draw from the buffer.
+    /// Fails if `storage` can't be encoded, or if the usages can't be combined.
+    pub fn try_add_storage_with_usage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        storage: &T,
+        extra_usage: BufferUsages,
+    ) -> Result<&mut Self> {
+        let contents = encode_storage(name.key(), storage)?;
+        self.add_buffer(
+            name,
+            contents,
+            BufferUsages::COPY_DST | BufferUsages::STORAGE | extra_usage,
+        )
+    }
 
-        self.buffers.insert(
-            format!("{name:?}"),
-            render_device.create_buffer_with_data(&BufferInitDescriptor {
-                label: Some(&format!("{name:?}")),
-                contents: buffer.as_ref(),
-                usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
-            }),
-        );
-        self
+    /// Like [`add_storage`](Self::add_storage), with `extra_usage` on top of the default usages.
+    /// In case of error, this function will panic.
+    pub fn add_storage_with_usage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        storage: &T,
+        extra_usage: BufferUsages,
+    ) -> &mut Self {
+        self.try_add_storage_with_usage(name, storage, extra_usage)
+            .unwrap()
     }
 
     /// Add a new read/write storage buffer to the worker, and fill it with `storage`.
+    /// Fails if `storage` can't be encoded.
+    pub fn try_add_rw_storage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        storage: &T,
+    ) -> Result<&mut Self> {
+        self.try_add_rw_storage_with_usage(name, storage, BufferUsages::empty())
+    }
+
+    /// Add a new read/write storage buffer to the worker, and fill it with `storage`.
+    /// In case of error, this function will panic.
     pub fn add_rw_storage<T: ShaderType + WriteInto>(&mut self, name: E, storage: &T) -> &mut Self {
-        let mut buffer = StorageBuffer::new(Vec::new());
-        buffer.write::<T>(storage).unwrap();
+        self.try_add_rw_storage(name, storage).unwrap()
+    }
+
+    /// Like [`try_add_rw_storage`](Self::try_add_rw_storage), with `extra_usage` on top of
+    /// the default usages, e.g. `VERTEX` or `INDIRECT` to draw from the buffer.
+    /// Fails if `storage` can't be encoded, or if the usages can't be combined.
+    pub fn try_add_rw_storage_with_usage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        storage: &T,
+        extra_usage: BufferUsages,
+    ) -> Result<&mut Self> {
+        let contents = encode_storage(name.key(), storage)?;
+        self.add_buffer(
+            name,
+            contents,
+            BufferUsages::COPY_DST | BufferUsages::COPY_SRC | BufferUsages::STORAGE | extra_usage,
+        )
+    }
+
+    /// Like [`add_rw_storage`](Self::add_rw_storage), with `extra_usage` on top of
+    /// the default usages.
+    /// In case of error, this function will panic.
+    pub fn add_rw_storage_with_usage<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        storage: &T,
+        extra_usage: BufferUsages,
+    ) -> &mut Self {
+        self.try_add_rw_storage_with_usage(name, storage, extra_usage)
+            .unwrap()
+    }
+
+    /// Create the buffer `name` filled with `contents`.
+    /// Fails if it is mappable along with other usages, which needs `MAPPABLE_PRIMARY_BUFFERS`.
+    fn add_buffer(&mut self, name: E, contents: Vec<u8>, usage: BufferUsages) -> Result<&mut Self> {
+        if usage.intersects(BufferUsages::MAP_READ | BufferUsages::MAP_WRITE)
+            && !self.mappable_primary_buffers()
+        {
+            return Err(Error::InvalidUsage(name.key().to_owned(), usage));
+        }
 
         let render_device = self.app.world.resource::<RenderDevice>();
 
         self.buffers.insert(
-            format!("{name:?}"),
+            name.key().to_owned(),
             render_device.create_buffer_with_data(&BufferInitDescriptor {
-                label: Some(&format!("{name:?}")),
-                contents: buffer.as_ref(),
-                usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+                label: Some(name.key()),
+                contents: &contents,
+                usage,
             }),
         );
-        self
+        self.initial_contents
+            .insert(name.key().to_owned(), contents);
+        Ok(self)
+    }
+
+    /// Create two staging buffers, one to read from and one to write to.
+    /// Additionally, it will create a read/write storage buffer to access from
+    /// your shaders.
+    /// The buffer will be filled with `data`
+    pub fn try_add_staging<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        data: &T,
+    ) -> Result<&mut Self> {
+        let contents = encode_storage(name.key(), data)?;
+        let capacity = contents.len() as u64;
+        self.add_staging_contents(name, contents, capacity);
+        Ok(self)
     }
 
     /// Create two staging buffers, one to read from and one to write to.
     /// Additionally, it will create a read/write storage buffer to access from
     /// your shaders.
     /// The buffer will be filled with `data`
+    /// In case of error, this function will panic.
     pub fn add_staging<T: ShaderType + WriteInto>(&mut self, name: E, data: &T) -> &mut Self {
-        self.add_rw_storage(name, data);
-        let buffer = self.buffers.get(&format!("{name:?}")).unwrap();
+        self.try_add_staging(name, data).unwrap()
+    }
+
+    /// Add an atomic `u32` counter starting at zero, e.g. the number of elements a pass emitted.
+    /// It is a staging buffer, bind it as `atomic<u32>` and read it with
+    /// [`AppComputeWorker::read_counter`].
+    pub fn add_atomic_counter(&mut self, name: E) -> &mut Self {
+        self.add_staging(name, &0u32)
+    }
+
+    /// Like [`try_add_staging`](Self::try_add_staging), but the buffers hold `capacity_bytes`,
+    /// leaving room for the data to grow. Only `data` is written, the rest is zeroed.
+    /// Fails if `data` can't be encoded or doesn't fit.
+    pub fn try_add_staging_with_capacity<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        data: &T,
+        capacity_bytes: u64,
+    ) -> Result<&mut Self> {
+        let contents = encode_storage(name.key(), data)?;
+        if contents.len() as u64 > capacity_bytes {
+            return Err(Error::CapacityTooSmall(
+                name.key().to_owned(),
+                contents.len() as u64,
+                capacity_bytes,
+            ));
+        }
+
+        self.add_staging_contents(name, contents, capacity_bytes);
+        Ok(self)
+    }
+
+    /// Like [`add_staging`](Self::add_staging), but the buffers hold `capacity_bytes`,
+    /// leaving room for the data to grow. Only `data` is written, the rest is zeroed.
+    /// In case of error, this function will panic.
+    pub fn add_staging_with_capacity<T: ShaderType + WriteInto>(
+        &mut self,
+        name: E,
+        data: &T,
+        capacity_bytes: u64,
+    ) -> &mut Self {
+        self.try_add_staging_with_capacity(name, data, capacity_bytes)
+            .unwrap()
+    }
 
+    /// Add a new read/write storage buffer holding indirect dispatch arguments,
+    /// the workgroup counts `[x, y, z]` of each set, see `add_pass_indirect`.
+    /// Passes may overwrite them, as `array<vec3<u32>>` isn't packed use `array<u32>` in shaders.
+    pub fn add_indirect_storage(&mut self, name: E, args: &[[u32; 3]]) -> &mut Self {
         let render_device = self.app.world.resource::<RenderDevice>();
 
-        let staging = StagingBuffer {
-            mapped: true,
-            buffer: render_device.create_buffer(&BufferDescriptor {
-                label: Some(&format!("{name:?}")),
-                size: buffer.size(),
-                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-                mapped_at_creation: true,
+        let contents = cast_slice(args).to_vec();
+        self.buffers.insert(
+            name.key().to_owned(),
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some(name.key()),
+                contents: &contents,
+                usage: BufferUsages::COPY_DST
+                    | BufferUsages::COPY_SRC
+                    | BufferUsages::STORAGE
+                    | BufferUsages::INDIRECT,
             }),
-        };
+        );
+        self.initial_contents
+            .insert(name.key().to_owned(), contents);
+        self
+    }
 
-        self.staging_buffers.insert(format!("{name:?}"), staging);
+    /// Add a new texture to the worker, bound to passes as a view of the whole texture,
+    /// e.g. a `texture_storage_2d` or a `texture_2d`. `descriptor` must have the usages of
+    /// these bindings, and `COPY_SRC` to be read back with `read_texture`.
+    pub fn add_texture(&mut self, name: E, descriptor: TextureDescriptor) -> &mut Self {
+        let render_device = self.app.world.resource::<RenderDevice>();
 
+        let texture = render_device.create_texture(&descriptor);
+        self.insert_texture(name, texture);
         self
     }
 
+    /// Add a new texture to the worker, filled with the image behind `handle`.
+    /// The texture can be sampled, copied from and to, and bound as a storage texture
+    /// if its format allows it.
+    /// The image must already be in `Assets<Image>`, e.g. created in code,
+    /// as images loaded by the `AssetServer` aren't yet when the worker is built.
+    pub fn try_add_texture_from_image(
+        &mut self,
+        name: E,
+        handle: &Handle<Image>,
+    ) -> Result<&mut Self> {
+        let Some(image) = self.app.world.resource::<Assets<Image>>().get(handle) else {
+            return Err(Error::ImageNotFound(name.key().to_owned()));
+        };
+        let render_device = self.app.world.resource::<RenderDevice>();
+        let render_queue = self.app.world.resource::<RenderQueue>();
+
+        let mut descriptor = image.texture_descriptor.clone();
+        descriptor.label = None;
+        descriptor.usage |=
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC | TextureUsages::COPY_DST;
+        let storage = descriptor
+            .format
+            .guaranteed_format_features(render_device.features())
+            .allowed_usages
+            .contains(TextureUsages::STORAGE_BINDING);
+        if storage {
+            descriptor.usage |= TextureUsages::STORAGE_BINDING;
+        }
+
+        let texture = render_device.create_texture_with_data(
+            render_queue,
+            &descriptor,
+            TextureDataOrder::default(),
+            &image.data,
+        );
+        self.insert_texture(name, texture);
+        Ok(self)
+    }
+
+    /// Add a new texture to the worker, filled with the image behind `handle`.
+    /// In case of error, this function will panic.
+    pub fn add_texture_from_image(&mut self, name: E, handle: &Handle<Image>) -> &mut Self {
+        self.try_add_texture_from_image(name, handle).unwrap()
+    }
+
+    fn insert_texture(&mut self, name: E, texture: Texture) {
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        self.textures
+            .insert(name.key().to_owned(), BoundTexture { texture, view });
+    }
+
     /// Add a new empty uniform buffer to the worker.
     pub fn add_empty_uniform(&mut self, name: E, size: u64) -> &mut Self {
         let render_device = self.app.world.resource::<RenderDevice>();
 
         self.buffers.insert(
-            format!("{name:?}"),
+            name.key().to_owned(),
             render_device.create_buffer(&BufferDescriptor {
-                label: Some(&format!("{name:?}")),
+                label: Some(name.key()),
                 size,
                 usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
                 mapped_at_creation: false,
@@ -167,9 +488,9 @@ impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
         let render_device = self.app.world.resource::<RenderDevice>();
 
         self.buffers.insert(
-            format!("{name:?}"),
+            name.key().to_owned(),
             render_device.create_buffer(&BufferDescriptor {
-                label: Some(&format!("{name:?}")),
+                label: Some(name.key()),
                 size,
                 usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
                 mapped_at_creation: false,
@@ -178,14 +499,45 @@ impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
         self
     }
 
+    /// Add a new empty storage buffer to the worker, cleared on the GPU before the first run
+    /// so the first dispatch reads zeros whatever the backend. It will be read only.
+    /// The clear is forgotten if the field is replaced before the worker is built.
+    /// Fails if `size` isn't a multiple of 4.
+    pub fn try_add_empty_storage_zeroed(&mut self, name: E, size: u64) -> Result<&mut Self> {
+        if !size.is_multiple_of(COPY_BUFFER_ALIGNMENT) {
+            return Err(Error::MisalignedSize(name.key().to_owned(), size));
+        }
+        self.add_empty_storage(name, size);
+        let id = self.buffers[name.key()].id();
+        self.zeroed.insert(name.key().to_owned(), id);
+        Ok(self)
+    }
+
+    /// Add a new empty storage buffer to the worker, cleared on the GPU before the first run.
+    /// In case of error, this function will panic.
+    pub fn add_empty_storage_zeroed(&mut self, name: E, size: u64) -> &mut Self {
+        self.try_add_empty_storage_zeroed(name, size).unwrap()
+    }
+
+    /// Add a new zeroed storage buffer holding `count` elements of type `T`. It will be read only.
+    /// The size follows the layout of `array<T>`, element padding included.
+    pub fn add_zeroed_storage<T: ShaderType + ShaderSize>(
+        &mut self,
+        name: E,
+        count: u64,
+    ) -> &mut Self {
+        let stride = <[T; 1]>::min_size().get();
+        self.add_empty_storage_zeroed(name, count * stride)
+    }
+
     /// Add a new empty read/write storage buffer to the worker.
     pub fn add_empty_rw_storage(&mut self, name: E, size: u64) -> &mut Self {
         let render_device = self.app.world.resource::<RenderDevice>();
 
         self.buffers.insert(
-            format!("{name:?}"),
+            name.key().to_owned(),
             render_device.create_buffer(&BufferDescriptor {
-                label: Some(&format!("{name:?}")),
+                label: Some(name.key()),
                 size,
                 usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC | BufferUsages::STORAGE,
                 mapped_at_creation: false,
@@ -194,53 +546,846 @@ impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
         self
     }
 
+    /// Bind only the first `size` bytes of `name` instead of the whole buffer.
+    /// Useful when a buffer is over-allocated and the shader relies on `arrayLength`
+    /// to know how many elements are valid.
+    pub fn set_binding_size(&mut self, name: E, size: u64) -> &mut Self {
+        self.binding_sizes.insert(name.key().to_owned(), size);
+        self
+    }
+
+    /// Add a buffer receiving the first `count` results of `query_set`, e.g. GPU timestamps.
+    /// The queries are resolved at the end of every run, and the results can then be
+    /// read like a staging buffer, as one `u64` per query.
+    pub fn add_query_buffer(&mut self, name: E, query_set: QuerySet, count: u32) -> &mut Self {
+        let render_device = self.app.world.resource::<RenderDevice>();
+
+        let size = count as u64 * QUERY_SIZE as u64;
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some(name.key()),
+            size,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = StagingBuffer {
+            mapped: true,
+            buffer: render_device.create_buffer(&BufferDescriptor {
+                label: Some(name.key()),
+                size,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: true,
+            }),
+            primary: false,
+        };
+
+        self.buffers.insert(name.key().to_owned(), buffer);
+        self.staging_buffers.insert(name.key().to_owned(), staging);
+        self.query_sets
+            .insert(name.key().to_owned(), (Arc::new(query_set), count));
+        self
+    }
+
+    /// Add a buffer created elsewhere, e.g. shared with a render pipeline, to the worker.
+    /// The buffer is used as is: you are responsible for giving it the usages your passes need,
+    /// like `COPY_DST` to write to it, or `COPY_SRC` to read it back.
+    /// Its contents are yours too: `reset` leaves them untouched.
+    pub fn add_existing_buffer(&mut self, name: E, buffer: Buffer) -> &mut Self {
+        self.existing_buffers.insert(buffer.id());
+        self.buffers.insert(name.key().to_owned(), buffer);
+        self
+    }
+
+    /// Add a read/write storage buffer holding `count` random number generator states,
+    /// one `u32` per invocation, derived from `seed` with splitmix64.
+    /// Use it from your shader as an `array<u32>`, advancing each state with e.g. PCG or xorshift.
+    pub fn add_rng_state(&mut self, name: E, count: u32, seed: u64) -> &mut Self {
+        let seeds = splitmix64_seeds(count, seed);
+
+        let render_device = self.app.world.resource::<RenderDevice>();
+
+        self.buffers.insert(
+            name.key().to_owned(),
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some(name.key()),
+                contents: cast_slice(&seeds),
+                usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+            }),
+        );
+        self.initial_contents
+            .insert(name.key().to_owned(), cast_slice(&seeds).to_vec());
+        self
+    }
+
+    /// Add a scratch read/write storage buffer of `size` bytes to the worker.
+    ///
+    /// Scratch buffers are meant for temporary data living within a single pass:
+    /// they can't be read back nor written from the CPU, and their content is undefined
+    /// at the start of each pass.
+    /// All scratch buffers of the same size share the same GPU memory, so two scratch buffers
+    /// of the same size can't be bound in one pass, `try_build` fails if they are.
+    pub fn add_scratch(&mut self, name: E, size: u64) -> &mut Self {
+        let render_device = self.app.world.resource::<RenderDevice>();
+
+        let buffer = self
+            .scratch_buffers
+            .entry(size)
+            .or_insert_with(|| {
+                render_device.create_buffer(&BufferDescriptor {
+                    label: Some(&format!("scratch_{size}")),
+                    size,
+                    usage: BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                })
+            })
+            .clone();
+
+        self.buffers.insert(name.key().to_owned(), buffer);
+        self
+    }
+
     /// Create two staging buffers, one to read from and one to write to.
     /// Additionally, it will create a read/write storage buffer to access from
     /// your shaders.
     /// The buffer will empty.
-    pub fn add_empty_staging(&mut self, name: E, size: u64) -> &mut Self {
+    pub fn try_add_empty_staging(&mut self, name: E, size: u64) -> Result<&mut Self> {
+        if self.mappable_primary_buffers() {
+            self.add_primary_staging(name, size, None);
+            return Ok(self);
+        }
+
         self.add_empty_rw_storage(name, size);
 
-        let buffer = self.buffers.get(&format!("{name:?}")).unwrap();
+        let Some(buffer) = self.buffers.get(name.key()) else {
+            return Err(Error::BufferNotFound(name.key().to_owned()));
+        };
 
         let render_device = self.app.world.resource::<RenderDevice>();
 
         let staging = StagingBuffer {
             mapped: true,
             buffer: render_device.create_buffer(&BufferDescriptor {
-                label: Some(&format!("{name:?}")),
+                label: Some(name.key()),
                 size: buffer.size(),
                 usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
                 mapped_at_creation: true,
             }),
+            primary: false,
         };
 
-        self.staging_buffers.insert(format!("{name:?}"), staging);
+        self.staging_buffers.insert(name.key().to_owned(), staging);
 
-        self
+        Ok(self)
+    }
+
+    /// Whether storage buffers can be mapped directly, see `add_primary_staging`.
+    fn mappable_primary_buffers(&self) -> bool {
+        self.app
+            .world
+            .resource::<RenderDevice>()
+            .features()
+            .contains(Features::MAPPABLE_PRIMARY_BUFFERS)
+    }
+
+    /// With `MAPPABLE_PRIMARY_BUFFERS`, a staging field needs no separate staging buffer:
+    /// its read/write storage buffer is mapped directly, saving memory and a copy per run.
+    /// Bevy only enables this feature if requested in its `WgpuSettings`.
+    fn add_primary_staging(&mut self, name: E, size: u64, contents: Option<&[u8]>) {
+        let render_device = self.app.world.resource::<RenderDevice>();
+
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some(name.key()),
+            size,
+            usage: BufferUsages::MAP_READ
+                | BufferUsages::COPY_DST
+                | BufferUsages::COPY_SRC
+                | BufferUsages::STORAGE,
+            mapped_at_creation: true,
+        });
+        if let Some(contents) = contents {
+            buffer
+                .slice(..)
+                .get_mapped_range_mut()
+                .copy_from_slice(contents);
+        }
+
+        self.buffers.insert(name.key().to_owned(), buffer.clone());
+        self.staging_buffers.insert(
+            name.key().to_owned(),
+            StagingBuffer {
+                mapped: true,
+                buffer,
+                primary: true,
+            },
+        );
+    }
+
+    /// Add a staging field of `capacity` bytes, starting with `contents` and zeroed after.
+    fn add_staging_contents(&mut self, name: E, contents: Vec<u8>, capacity: u64) {
+        let mut padded = contents;
+        padded.resize(capacity as usize, 0);
+
+        if self.mappable_primary_buffers() {
+            self.add_primary_staging(name, capacity, Some(&padded));
+        } else {
+            let render_device = self.app.world.resource::<RenderDevice>();
+
+            let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some(name.key()),
+                contents: &padded,
+                usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC | BufferUsages::STORAGE,
+            });
+
+            // The staging buffer holds the initial data until the first run, so it can be read right away
+            let staging = StagingBuffer {
+                mapped: true,
+                buffer: render_device.create_buffer(&BufferDescriptor {
+                    label: Some(name.key()),
+                    size: buffer.size(),
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: true,
+                }),
+                primary: false,
+            };
+            staging.buffer.slice(..).get_mapped_range_mut()[..padded.len()]
+                .copy_from_slice(&padded);
+
+            self.buffers.insert(name.key().to_owned(), buffer);
+            self.staging_buffers.insert(name.key().to_owned(), staging);
+        }
+
+        // The whole buffer is restored on reset, including the zeroed capacity
+        self.initial_contents.insert(name.key().to_owned(), padded);
+    }
+
+    /// Create two staging buffers, one to read from and one to write to.
+    /// Additionally, it will create a read/write storage buffer to access from
+    /// your shaders.
+    /// The buffer will empty.
+    /// In case of error, this function will panic.
+    pub fn add_empty_staging(&mut self, name: E, size: u64) -> &mut Self {
+        self.try_add_empty_staging(name, size).unwrap()
+    }
+
+    /// The typed marker of `name`, added with `add_staging` or one of its variants.
+    /// Fails if `name` has no staging buffer.
+    pub fn try_staging_field(&self, name: E) -> Result<StagingField<E>> {
+        if !self.staging_buffers.contains_key(name.key()) {
+            return Err(Error::StagingBufferNotFound(name.key().to_owned()));
+        }
+        Ok(StagingField(name))
+    }
+
+    /// The typed marker of `name`, added with `add_staging` or one of its variants.
+    /// In case of error, this function will panic.
+    pub fn staging_field(&self, name: E) -> StagingField<E> {
+        self.try_staging_field(name).unwrap()
+    }
+
+    /// The typed marker of `name`, added with `add_uniform` or one of its variants.
+    /// Fails if `name` isn't a uniform buffer which can be written to.
+    pub fn try_uniform_field(&self, name: E) -> Result<UniformField<E>> {
+        self.check_field_usage(
+            name,
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            "uniform",
+        )?;
+        Ok(UniformField(name))
+    }
+
+    /// The typed marker of `name`, added with `add_uniform` or one of its variants.
+    /// In case of error, this function will panic.
+    pub fn uniform_field(&self, name: E) -> UniformField<E> {
+        self.try_uniform_field(name).unwrap()
+    }
+
+    /// The typed marker of `name`, added with `add_storage` or one of its variants.
+    /// Fails if `name` isn't a storage buffer which can be written to.
+    pub fn try_storage_field(&self, name: E) -> Result<StorageField<E>> {
+        self.check_field_usage(
+            name,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            "storage",
+        )?;
+        Ok(StorageField(name))
+    }
+
+    /// The typed marker of `name`, added with `add_storage` or one of its variants.
+    /// In case of error, this function will panic.
+    pub fn storage_field(&self, name: E) -> StorageField<E> {
+        self.try_storage_field(name).unwrap()
+    }
+
+    /// The typed marker of `name`, added with `add_rw_storage` or one of its variants.
+    /// Fails if `name` isn't a storage buffer which can be both written to and read back.
+    pub fn try_rw_storage_field(&self, name: E) -> Result<RwStorageField<E>> {
+        self.check_field_usage(
+            name,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            "read/write storage",
+        )?;
+        Ok(RwStorageField(name))
+    }
+
+    /// The typed marker of `name`, added with `add_rw_storage` or one of its variants.
+    /// In case of error, this function will panic.
+    pub fn rw_storage_field(&self, name: E) -> RwStorageField<E> {
+        self.try_rw_storage_field(name).unwrap()
+    }
+
+    fn check_field_usage(&self, name: E, usage: BufferUsages, kind: &'static str) -> Result<()> {
+        let Some(buffer) = self.buffers.get(name.key()) else {
+            return Err(Error::BufferNotFound(name.key().to_owned()));
+        };
+        if !buffer.usage().contains(usage) {
+            return Err(Error::WrongFieldKind(name.key().to_owned(), kind));
+        }
+        Ok(())
+    }
+
+    /// Add a new compute pass to your worker.
+    /// They will run sequentially in the order you insert them.
+    ///
+    /// Every pass is recorded as its own compute pass, and wgpu inserts the needed
+    /// memory barriers between them. A pass binding the same read/write buffer as the
+    /// previous one will always see its writes, so in-place algorithms need no extra synchronization.
+    ///
+    /// Fails if the shader or one of its dependencies can't be found, or isn't written in WGSL,
+    /// or if `dispatch_size` exceeds the device's `max_compute_workgroups_per_dimension`.
+    pub fn try_add_pass<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+    ) -> Result<&mut Self> {
+        self.try_add_pass_multi::<S>(dispatch_size, &[vars])
+    }
+
+    /// Add a new compute pass to your worker, binding each slice of `groups` to its own
+    /// bind group: the first one to `@group(0)`, the second one to `@group(1)`, and so on.
+    /// Useful to share a group of fields, e.g. a uniform, across shaders.
+    /// Only group 0 can have an explicit layout, the others are reflected from the shader.
+    pub fn try_add_pass_multi<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        groups: &[&[E]],
+    ) -> Result<&mut Self> {
+        check_dispatch_size(self.app.world.resource::<RenderDevice>(), dispatch_size)?;
+        let pipeline_key = self.try_queue_pipeline::<S>(None, &[])?;
+
+        let mut groups = groups.iter().map(|vars| keys(vars));
+
+        let vars = groups.next().unwrap_or_default();
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            dispatch_size,
+            extra_groups: groups.collect(),
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, vars)
+        })));
+        Ok(self)
+    }
+
+    /// Add a new compute pass to your worker, binding each slice of `groups` to its own bind group.
+    /// In case of error, this function will panic.
+    pub fn add_pass_multi<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        groups: &[&[E]],
+    ) -> &mut Self {
+        self.try_add_pass_multi::<S>(dispatch_size, groups).unwrap()
     }
 
     /// Add a new compute pass to your worker.
     /// They will run sequentially in the order you insert them.
+    /// In case of error, this function will panic.
     pub fn add_pass<S: ComputeShader>(&mut self, dispatch_size: [u32; 3], vars: &[E]) -> &mut Self {
-        if !self.cached_pipeline_ids.contains_key(S::type_path()) {
-            S::dependencies()
-                .into_iter()
-                .for_each(|shader| match shader {
+        self.try_add_pass::<S>(dispatch_size, vars).unwrap()
+    }
+
+    /// Insert a new compute pass before the step at `index`, or at the end if `index` is the
+    /// number of steps, e.g. to run a debug pass between two existing ones.
+    /// Fails if `index` is out of bounds, or like `try_add_pass`.
+    pub fn try_insert_pass_at<S: ComputeShader>(
+        &mut self,
+        index: usize,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+    ) -> Result<&mut Self> {
+        if index > self.steps.len() {
+            return Err(Error::StepOutOfBounds(index));
+        }
+
+        self.try_add_pass::<S>(dispatch_size, vars)?;
+        self.move_last_step_to(index);
+        Ok(self)
+    }
+
+    /// Insert a new compute pass before the step at `index`.
+    /// In case of error, this function will panic.
+    pub fn insert_pass_at<S: ComputeShader>(
+        &mut self,
+        index: usize,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+    ) -> &mut Self {
+        self.try_insert_pass_at::<S>(index, dispatch_size, vars)
+            .unwrap()
+    }
+
+    /// Add a new compute pass to your worker, dispatched with enough workgroups
+    /// to run one invocation per element, according to [`ComputeShader::workgroup_size`].
+    /// The shader must skip the extra invocations of the last workgroups.
+    /// Fails if the workgroup size has a zero, or doesn't match the `@workgroup_size` of the shader.
+    pub fn try_add_pass_for_elements<S: ComputeShader>(
+        &mut self,
+        elements: [u32; 3],
+        vars: &[E],
+    ) -> Result<&mut Self> {
+        let pipeline_key = self.try_queue_pipeline::<S>(None, &[])?;
+        let dispatch_size = workgroups_for::<S>(elements, self.workgroup_sizes.get(&pipeline_key))?;
+        self.try_add_pass::<S>(dispatch_size, vars)
+    }
+
+    /// Add a new compute pass to your worker, dispatched with enough workgroups
+    /// to run one invocation per element.
+    /// In case of error, this function will panic.
+    pub fn add_pass_for_elements<S: ComputeShader>(
+        &mut self,
+        elements: [u32; 3],
+        vars: &[E],
+    ) -> &mut Self {
+        self.try_add_pass_for_elements::<S>(elements, vars).unwrap()
+    }
+
+    /// Add a new compute pass to your worker, whose dispatch size is computed by `size_fn`
+    /// right before every dispatch, e.g. from [`buffer_size`](AppComputeWorker::buffer_size).
+    pub fn try_add_pass_dynamic_size<S: ComputeShader>(
+        &mut self,
+        vars: &[E],
+        size_fn: impl Fn(&AppComputeWorker<W>) -> [u32; 3] + Send + Sync + 'static,
+    ) -> Result<&mut Self> {
+        let pipeline_key = self.try_queue_pipeline::<S>(None, &[])?;
+
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            dynamic_size: Some(DynamicSize::new(size_fn)),
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, keys(vars))
+        })));
+        Ok(self)
+    }
+
+    /// Add a new compute pass to your worker, whose dispatch size is computed by `size_fn`
+    /// right before every dispatch.
+    /// In case of error, this function will panic.
+    pub fn add_pass_dynamic_size<S: ComputeShader>(
+        &mut self,
+        vars: &[E],
+        size_fn: impl Fn(&AppComputeWorker<W>) -> [u32; 3] + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.try_add_pass_dynamic_size::<S>(vars, size_fn).unwrap()
+    }
+
+    /// Add a new compute pass to your worker, whose shader is compiled with `shader_defs`
+    /// on top of [`ComputeShader::shader_defs`]. Passes of the same shader with different
+    /// defs get their own pipeline, so they don't need a marker type each.
+    pub fn try_add_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<&mut Self> {
+        check_dispatch_size(self.app.world.resource::<RenderDevice>(), dispatch_size)?;
+        let pipeline_key = self.try_queue_pipeline::<S>(None, shader_defs)?;
+
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            dispatch_size,
+            shader_defs: shader_defs_text(shader_defs),
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, keys(vars))
+        })));
+        Ok(self)
+    }
+
+    /// Add a new compute pass to your worker, whose shader is compiled with `shader_defs`
+    /// on top of [`ComputeShader::shader_defs`].
+    /// In case of error, this function will panic.
+    pub fn add_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> &mut Self {
+        self.try_add_pass_with_defs::<S>(dispatch_size, vars, shader_defs)
+            .unwrap()
+    }
+
+    /// Add a new compute pass to your worker, running the `entry` function of the shader
+    /// instead of [`ComputeShader::entry_point`], e.g. to run the `init`, `step` and `finalize`
+    /// entry points of one shader file as separate passes.
+    pub fn try_add_pass_entry<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        entry: &str,
+        vars: &[E],
+    ) -> Result<&mut Self> {
+        check_dispatch_size(self.app.world.resource::<RenderDevice>(), dispatch_size)?;
+        // The shader's own entry point is the same pipeline as a regular pass
+        let entry_point = (entry != S::entry_point()).then(|| entry.to_owned());
+        let pipeline_key = self.try_queue_pipeline::<S>(entry_point.as_deref(), &[])?;
+
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            dispatch_size,
+            entry_point,
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, keys(vars))
+        })));
+        Ok(self)
+    }
+
+    /// Add a new compute pass to your worker, running the `entry` function of the shader.
+    /// In case of error, this function will panic.
+    pub fn add_pass_entry<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        entry: &str,
+        vars: &[E],
+    ) -> &mut Self {
+        self.try_add_pass_entry::<S>(dispatch_size, entry, vars)
+            .unwrap()
+    }
+
+    /// Add a new compute pass to your worker, with a human readable `label`.
+    /// The label is used for the compute pass descriptor, so it shows up in GPU captures.
+    pub fn try_add_labeled_pass<S: ComputeShader>(
+        &mut self,
+        label: &str,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+    ) -> Result<&mut Self> {
+        self.try_add_labeled_pass_with_defs::<S>(label, dispatch_size, vars, &[])
+    }
+
+    /// Add a new compute pass to your worker, with a human readable `label`, whose shader
+    /// is compiled with `shader_defs` on top of [`ComputeShader::shader_defs`].
+    pub fn try_add_labeled_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        label: &str,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<&mut Self> {
+        check_dispatch_size(self.app.world.resource::<RenderDevice>(), dispatch_size)?;
+        let pipeline_key = self.try_queue_pipeline::<S>(None, shader_defs)?;
+
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            dispatch_size,
+            shader_defs: shader_defs_text(shader_defs),
+            label: Some(label.to_owned()),
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, keys(vars))
+        })));
+        Ok(self)
+    }
+
+    /// Add a new compute pass to your worker, with a human readable `label`.
+    /// In case of error, this function will panic.
+    pub fn add_labeled_pass<S: ComputeShader>(
+        &mut self,
+        label: &str,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+    ) -> &mut Self {
+        self.try_add_labeled_pass::<S>(label, dispatch_size, vars)
+            .unwrap()
+    }
+
+    /// Add a new compute pass to your worker, with a human readable `label`, whose shader
+    /// is compiled with `shader_defs` on top of [`ComputeShader::shader_defs`].
+    /// In case of error, this function will panic.
+    pub fn add_labeled_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        label: &str,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> &mut Self {
+        self.try_add_labeled_pass_with_defs::<S>(label, dispatch_size, vars, shader_defs)
+            .unwrap()
+    }
+
+    /// Add a compute pass dispatched `count` times, e.g. for parameter sweeps.
+    /// The `param` buffer packs one parameter set every `stride` bytes, and the i-th dispatch
+    /// sees the i-th set, bound with a dynamic offset of `i * stride`.
+    ///
+    /// Reflected layouts never use dynamic offsets, so the shader must declare its
+    /// [`layouts()`](ComputeShader::layouts), with `has_dynamic_offset: true` for `param`.
+    /// Fails if `stride` isn't a multiple of the device's offset alignment (usually 256 bytes).
+    pub fn try_add_instanced_pass<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        param: E,
+        count: u32,
+        stride: u32,
+    ) -> Result<&mut Self> {
+        self.try_add_instanced_pass_with_defs::<S>(dispatch_size, vars, param, count, stride, &[])
+    }
+
+    /// Add a compute pass dispatched `count` times, like `try_add_instanced_pass`, whose shader
+    /// is compiled with `shader_defs` on top of [`ComputeShader::shader_defs`].
+    pub fn try_add_instanced_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        param: E,
+        count: u32,
+        stride: u32,
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<&mut Self> {
+        let limits = self.app.world.resource::<RenderDevice>().limits();
+        let alignment = limits
+            .min_uniform_buffer_offset_alignment
+            .max(limits.min_storage_buffer_offset_alignment);
+        if stride == 0 || !stride.is_multiple_of(alignment) {
+            return Err(Error::MisalignedStride(param.key().to_owned(), stride));
+        }
+        check_dispatch_size(self.app.world.resource::<RenderDevice>(), dispatch_size)?;
+
+        let pipeline_key = self.try_queue_pipeline::<S>(None, shader_defs)?;
+
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            dispatch_size,
+            shader_defs: shader_defs_text(shader_defs),
+            instances: Some(Instances {
+                param: param.key().to_owned(),
+                count,
+                stride,
+            }),
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, keys(vars))
+        })));
+        Ok(self)
+    }
+
+    /// Add a compute pass dispatched `count` times, each with the parameter set found
+    /// at `i * stride` in the `param` buffer.
+    /// In case of error, this function will panic.
+    pub fn add_instanced_pass<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        param: E,
+        count: u32,
+        stride: u32,
+    ) -> &mut Self {
+        self.try_add_instanced_pass::<S>(dispatch_size, vars, param, count, stride)
+            .unwrap()
+    }
+
+    /// Add a compute pass dispatched `count` times, whose shader is compiled with `shader_defs`
+    /// on top of [`ComputeShader::shader_defs`].
+    /// In case of error, this function will panic.
+    pub fn add_instanced_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        dispatch_size: [u32; 3],
+        vars: &[E],
+        param: E,
+        count: u32,
+        stride: u32,
+        shader_defs: &[ShaderDefVal],
+    ) -> &mut Self {
+        self.try_add_instanced_pass_with_defs::<S>(
+            dispatch_size,
+            vars,
+            param,
+            count,
+            stride,
+            shader_defs,
+        )
+        .unwrap()
+    }
+
+    /// Add a compute pass whose dispatch size is computed on the GPU from an element count,
+    /// e.g. an atomic counter incremented by an earlier pass.
+    /// The pass is dispatched with `ceil(count / workgroup_size)` workgroups along x,
+    /// `workgroup_size` being the x workgroup size of your shader.
+    /// `count` must be a storage buffer holding a single `u32`.
+    pub fn try_add_indirect_count_pass<S: ComputeShader>(
+        &mut self,
+        count: E,
+        workgroup_size: u32,
+        vars: &[E],
+    ) -> Result<&mut Self> {
+        self.try_add_indirect_count_pass_with_defs::<S>(count, workgroup_size, vars, &[])
+    }
+
+    /// Add a compute pass whose dispatch size is computed on the GPU from an element count,
+    /// like `try_add_indirect_count_pass`, and whose shader is compiled with `shader_defs`
+    /// on top of [`ComputeShader::shader_defs`].
+    pub fn try_add_indirect_count_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        count: E,
+        workgroup_size: u32,
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<&mut Self> {
+        let pipeline_key = self.try_queue_pipeline::<S>(None, shader_defs)?;
+
+        let render_device = self.app.world.resource::<RenderDevice>();
+        let indirect_count =
+            IndirectCount::new(render_device, count.key().to_owned(), workgroup_size);
+
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            shader_defs: shader_defs_text(shader_defs),
+            indirect_count: Some(indirect_count),
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, keys(vars))
+        })));
+        Ok(self)
+    }
+
+    /// Add a compute pass dispatched with `ceil(count / workgroup_size)` workgroups,
+    /// `count` being computed by an earlier pass.
+    /// In case of error, this function will panic.
+    pub fn add_indirect_count_pass<S: ComputeShader>(
+        &mut self,
+        count: E,
+        workgroup_size: u32,
+        vars: &[E],
+    ) -> &mut Self {
+        self.try_add_indirect_count_pass::<S>(count, workgroup_size, vars)
+            .unwrap()
+    }
+
+    /// Add a compute pass dispatched with `ceil(count / workgroup_size)` workgroups,
+    /// whose shader is compiled with `shader_defs` on top of [`ComputeShader::shader_defs`].
+    /// In case of error, this function will panic.
+    pub fn add_indirect_count_pass_with_defs<S: ComputeShader>(
+        &mut self,
+        count: E,
+        workgroup_size: u32,
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> &mut Self {
+        self.try_add_indirect_count_pass_with_defs::<S>(count, workgroup_size, vars, shader_defs)
+            .unwrap()
+    }
+
+    /// Add a compute pass dispatched indirectly, with the workgroup counts `[x, y, z]`
+    /// read from `indirect_buffer` at `offset` when the pass runs, e.g. written by an earlier pass.
+    /// The buffer must be added with `add_indirect_storage`, and `offset` be a multiple of 4.
+    pub fn try_add_pass_indirect<S: ComputeShader>(
+        &mut self,
+        indirect_buffer: E,
+        offset: u64,
+        vars: &[E],
+    ) -> Result<&mut Self> {
+        self.try_add_pass_indirect_with_defs::<S>(indirect_buffer, offset, vars, &[])
+    }
+
+    /// Add a compute pass dispatched indirectly, like `try_add_pass_indirect`, whose shader
+    /// is compiled with `shader_defs` on top of [`ComputeShader::shader_defs`].
+    pub fn try_add_pass_indirect_with_defs<S: ComputeShader>(
+        &mut self,
+        indirect_buffer: E,
+        offset: u64,
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<&mut Self> {
+        let name = indirect_buffer.key().to_owned();
+        let Some(buffer) = self.buffers.get(&name) else {
+            return Err(Error::BufferNotFound(name));
+        };
+        let fits = offset.is_multiple_of(4) && offset + INDIRECT_ARGS_SIZE <= buffer.size();
+        if !buffer.usage().contains(BufferUsages::INDIRECT) || !fits {
+            return Err(Error::InvalidIndirectBuffer(name, offset));
+        }
+
+        let pipeline_key = self.try_queue_pipeline::<S>(None, shader_defs)?;
+
+        self.steps.push(Step::ComputePass(Box::new(ComputePass {
+            shader_defs: shader_defs_text(shader_defs),
+            indirect_args: Some((name, offset)),
+            ..ComputePass::new(S::type_path().to_string(), pipeline_key, keys(vars))
+        })));
+        Ok(self)
+    }
+
+    /// Add a compute pass dispatched indirectly, with the workgroup counts read from
+    /// `indirect_buffer` at `offset` when the pass runs.
+    /// In case of error, this function will panic.
+    pub fn add_pass_indirect<S: ComputeShader>(
+        &mut self,
+        indirect_buffer: E,
+        offset: u64,
+        vars: &[E],
+    ) -> &mut Self {
+        self.try_add_pass_indirect::<S>(indirect_buffer, offset, vars)
+            .unwrap()
+    }
+
+    /// Add a compute pass dispatched indirectly, whose shader is compiled with `shader_defs`
+    /// on top of [`ComputeShader::shader_defs`].
+    /// In case of error, this function will panic.
+    pub fn add_pass_indirect_with_defs<S: ComputeShader>(
+        &mut self,
+        indirect_buffer: E,
+        offset: u64,
+        vars: &[E],
+        shader_defs: &[ShaderDefVal],
+    ) -> &mut Self {
+        self.try_add_pass_indirect_with_defs::<S>(indirect_buffer, offset, vars, shader_defs)
+            .unwrap()
+    }
+
+    /// Queue the pipeline of `S` compiled with `shader_defs` on top of its own, and with
+    /// `entry_point` instead of its own if given, unless it already is, and return the key
+    /// of this variant.
+    fn try_queue_pipeline<S: ComputeShader>(
+        &mut self,
+        entry_point: Option<&str>,
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<String> {
+        // Every variant of a shader gets its own pipeline and bind group layout
+        let pipeline_key =
+            pipeline_key(S::type_path(), entry_point, &shader_defs_text(shader_defs));
+        let entry_point = entry_point.unwrap_or(S::entry_point()).to_owned();
+
+        // Bind groups are built against the explicit layout when there is one
+        if let Some(layout) = S::layouts().first() {
+            self.bind_group_layouts
+                .insert(pipeline_key.clone(), layout.clone());
+        }
+
+        // Another worker may already have queued this shader, reuse its pipeline
+        if let Some(cached_id) = self
+            .app
+            .world
+            .get_resource::<AppPipelineCache>()
+            .and_then(|cache| cache.queued_pipelines.get(&pipeline_key).copied())
+        {
+            self.cached_pipeline_ids
+                .entry(pipeline_key.clone())
+                .or_insert(cached_id);
+        }
+
+        let queued = self.cached_pipeline_ids.contains_key(&pipeline_key);
+        if !queued {
+            for shader in S::dependencies() {
+                match shader {
                     ShaderRef::Default | ShaderRef::Handle(_) => {}
                     ShaderRef::Path(path) => {
-                        let path_string = path.path().to_str().unwrap();
+                        let path_string = path.path().to_string_lossy().into_owned();
 
-                        let mut current_directory = std::env::current_dir().unwrap();
+                        let mut current_directory = std::env::current_dir().map_err(Error::Io)?;
                         current_directory.push("assets");
-                        current_directory.push(path_string);
+                        current_directory.push(&path_string);
                         println!(
                             "Loading shader from path: {}",
                             current_directory.to_string_lossy()
                         );
 
-                        if current_directory.extension().unwrap() != "wgsl" {
-                            panic!("Only WGSL shaders are supported for now.");
+                        let extension = current_directory
+                            .extension()
+                            .and_then(|extension| extension.to_str())
+                            .unwrap_or_default()
+                            .to_owned();
+                        if !matches!(
+                            extension.as_str(),
+                            "wgsl" | "glsl" | "comp" | "vert" | "frag" | "spv"
+                        ) {
+                            return Err(Error::UnsupportedShaderFormat(path_string));
                         }
 
                         let mut hasher = DefaultHasher::new();
@@ -252,56 +1397,229 @@ impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
                         );
                         let handle = Handle::weak_from_u128(hash);
 
-                        let mut shader_string = String::new();
-                        let _ = File::open(current_directory)
-                            .unwrap()
-                            .read_to_string(&mut shader_string);
+                        let mut shader_bytes = Vec::new();
+                        File::open(current_directory)
+                            .map_err(|err| match err.kind() {
+                                ErrorKind::NotFound => Error::ShaderNotFound(path_string.clone()),
+                                _ => Error::Io(err),
+                            })?
+                            .read_to_end(&mut shader_bytes)
+                            .map_err(Error::Io)?;
+
+                        // GLSL and SPIR-V need Bevy's `shader_format_glsl` and `shader_format_spirv` features
+                        let shader = match extension.as_str() {
+                            "spv" => Shader::from_spirv(shader_bytes, path_string),
+                            _ => {
+                                let shader_string =
+                                    String::from_utf8(shader_bytes).map_err(|err| {
+                                        Error::Io(std::io::Error::new(ErrorKind::InvalidData, err))
+                                    })?;
+                                match extension.as_str() {
+                                    "glsl" | "comp" => Shader::from_glsl(
+                                        shader_string,
+                                        ShaderStage::Compute,
+                                        path_string,
+                                    ),
+                                    "vert" => Shader::from_glsl(
+                                        shader_string,
+                                        ShaderStage::Vertex,
+                                        path_string,
+                                    ),
+                                    "frag" => Shader::from_glsl(
+                                        shader_string,
+                                        ShaderStage::Fragment,
+                                        path_string,
+                                    ),
+                                    _ => Shader::from_wgsl(shader_string, path_string),
+                                }
+                            }
+                        };
 
                         let mut shader_assets = self.app.world.resource_mut::<Assets<Shader>>();
-                        //Frankly, this isn't great. It's forces the dependency to be written in WGSL.
-                        shader_assets.insert(handle, Shader::from_wgsl(shader_string, path_string));
+                        shader_assets.insert(handle, shader);
                     }
-                });
-
-            let shader = match S::shader() {
-                ShaderRef::Default => None,
-                ShaderRef::Handle(handle) => Some(handle),
-                ShaderRef::Path(path) => {
-                    let asset_server = self.app.world.resource::<AssetServer>();
-                    Some(asset_server.load(path))
                 }
             }
-            .unwrap();
+        }
+
+        let defs = S::shader_defs()
+            .iter()
+            .chain(shader_defs)
+            .cloned()
+            .collect::<Vec<_>>();
+        // The variant as compiled, `None` if it can't be parsed on its own
+        let module = self
+            .shader_source::<S>()?
+            .and_then(|source| parse_with_defs(&source, &defs));
+        if let Some(module) = &module {
+            if !queued {
+                self.validate_workgroup_storage::<S>(module, &entry_point)?;
+            }
+            // Checked against `ComputeShader::workgroup_size` when dispatching for elements
+            if let Some(workgroup_size) = declared_workgroup_size(module, &entry_point) {
+                self.workgroup_sizes
+                    .insert(pipeline_key.clone(), workgroup_size);
+            }
+        }
+
+        let shader = match S::shader() {
+            ShaderRef::Default => return Err(Error::ShaderNotFound(S::type_path().to_string())),
+            ShaderRef::Handle(handle) => handle,
+            ShaderRef::Path(path) => {
+                let asset_server = self.app.world.resource::<AssetServer>();
+                asset_server.load(path)
+            }
+        };
+        let descriptor = ComputePipelineDescriptor {
+            label: None,
+            layout: S::layouts().to_vec(),
+            push_constant_ranges: S::push_constant_ranges().to_vec(),
+            shader_defs: defs,
+            entry_point: Cow::Owned(entry_point),
+            shader,
+        };
+        // Kept to queue the pipeline again with other shader defs, see `set_shader_defs`
+        self.pipeline_sources.insert(
+            pipeline_key.clone(),
+            PipelineSource {
+                descriptor: descriptor.clone(),
+                pass_defs: shader_defs.to_vec(),
+            },
+        );
 
+        if !queued {
             let pipeline_cache = self
                 .app
                 .sub_app_mut(RenderApp)
                 .world
                 .resource::<PipelineCache>();
-            let cached_id = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-                label: None,
-                layout: S::layouts().to_vec(),
-                push_constant_ranges: S::push_constant_ranges().to_vec(),
-                shader_defs: S::shader_defs().to_vec(),
-                entry_point: Cow::Borrowed(S::entry_point()),
-                shader,
-            });
+            let cached_id = pipeline_cache.queue_compute_pipeline(descriptor);
 
             self.cached_pipeline_ids
-                .insert(S::type_path().to_string(), cached_id);
+                .insert(pipeline_key.clone(), cached_id);
+
+            if let Some(mut cache) = self.app.world.get_resource_mut::<AppPipelineCache>() {
+                cache
+                    .queued_pipelines
+                    .insert(pipeline_key.clone(), cached_id);
+            }
         }
 
-        self.steps.push(Step::ComputePass(ComputePass {
-            dispatch_size,
-            vars: vars.iter().map(|a| format!("{a:?}")).collect(),
-            shader_type_path: S::type_path().to_string(),
-        }));
-        self
+        Ok(pipeline_key)
+    }
+
+    /// Fail when `module`, a variant of `S` with its defs applied, uses more workgroup storage
+    /// than the device allows, instead of an opaque pipeline creation failure on some platforms.
+    fn validate_workgroup_storage<S: ComputeShader>(
+        &self,
+        module: &Module,
+        entry_point: &str,
+    ) -> Result<()> {
+        let Some(size) = workgroup_storage_size(module, entry_point) else {
+            return Ok(());
+        };
+
+        let limit = self
+            .app
+            .world
+            .resource::<RenderDevice>()
+            .limits()
+            .max_compute_workgroup_storage_size;
+        if size > limit {
+            return Err(Error::WorkgroupStorageTooLarge(
+                S::type_path().to_string(),
+                size,
+                limit,
+            ));
+        }
+        Ok(())
+    }
+
+    /// The WGSL source of `S`, if it is loaded or can be read from the assets folder.
+    fn shader_source<S: ComputeShader>(&self) -> Result<Option<String>> {
+        let source = match S::shader() {
+            ShaderRef::Default => None,
+            ShaderRef::Handle(handle) => self
+                .app
+                .world
+                .resource::<Assets<Shader>>()
+                .get(&handle)
+                .and_then(|shader| match &shader.source {
+                    Source::Wgsl(source) => Some(source.to_string()),
+                    _ => None,
+                }),
+            ShaderRef::Path(path) => {
+                let mut current_directory = std::env::current_dir().map_err(Error::Io)?;
+                current_directory.push("assets");
+                current_directory.push(path.path());
+                std::fs::read_to_string(current_directory).ok()
+            }
+        };
+        Ok(source)
     }
 
     pub fn add_swap(&mut self, buffer_a: E, buffer_b: E) -> &mut Self {
-        self.steps
-            .push(Step::Swap(format!("{buffer_a:?}"), format!("{buffer_b:?}")));
+        self.steps.push(Step::Swap(
+            buffer_a.key().to_owned(),
+            buffer_b.key().to_owned(),
+        ));
+        self
+    }
+
+    /// Insert a swap of `buffer_a` and `buffer_b` before the step at `index`,
+    /// or at the end if `index` is the number of steps.
+    /// Fails if `index` is out of bounds.
+    pub fn try_insert_swap_at(
+        &mut self,
+        index: usize,
+        buffer_a: E,
+        buffer_b: E,
+    ) -> Result<&mut Self> {
+        if index > self.steps.len() {
+            return Err(Error::StepOutOfBounds(index));
+        }
+
+        self.add_swap(buffer_a, buffer_b);
+        self.move_last_step_to(index);
+        Ok(self)
+    }
+
+    /// Insert a swap of `buffer_a` and `buffer_b` before the step at `index`.
+    /// In case of error, this function will panic.
+    pub fn insert_swap_at(&mut self, index: usize, buffer_a: E, buffer_b: E) -> &mut Self {
+        self.try_insert_swap_at(index, buffer_a, buffer_b).unwrap()
+    }
+
+    /// Move the step just added to `index`, which must be in bounds.
+    fn move_last_step_to(&mut self, index: usize) {
+        if let Some(step) = self.steps.pop() {
+            self.steps.insert(index, step);
+        }
+    }
+
+    /// Rotate the buffers behind `buffers`: the first one moves to the second field,
+    /// the second one to the third, and so on, the last one moving to the first field.
+    /// With two fields, this is the same as `add_swap`.
+    pub fn add_rotate(&mut self, buffers: &[E]) -> &mut Self {
+        self.steps.push(Step::Rotate(keys(buffers)));
+        self
+    }
+
+    /// Fill `buffer` with zeros on the GPU at this point of the run, e.g. to reset an accumulator.
+    /// The buffer needs the `COPY_DST` usage, which is checked when building the worker.
+    pub fn add_clear(&mut self, buffer: E) -> &mut Self {
+        self.steps.push(Step::Clear(buffer.key().to_owned()));
+        self
+    }
+
+    /// Run `f` between the steps around it, with the command encoder and the buffers,
+    /// to record commands the other steps don't cover, e.g. custom copies or clears.
+    /// Buffers are keyed by the name of their field, by [`ComputeFields::key`].
+    pub fn add_custom(
+        &mut self,
+        f: impl Fn(&mut CommandEncoder, &HashMap<String, Buffer>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.steps.push(Step::Custom(CustomStep::new(f)));
         self
     }
 
@@ -312,6 +1630,13 @@ impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
         self
     }
 
+    /// The worker will run every `n` frames, e.g. to halve the GPU load with `2`.
+    /// `0` is treated as `1`, running every frame.
+    pub fn every_n_frames(&mut self, n: u32) -> &mut Self {
+        self.run_mode = RunMode::EveryNFrames(n);
+        self
+    }
+
     /// The worker will run when requested.
     pub fn one_shot(&mut self) -> &mut Self {
         self.run_mode = RunMode::OneShot(false);
@@ -324,8 +1649,84 @@ impl<'a, W: ComputeWorker, E: Debug + Copy> AppComputeWorkerBuilder<'a, W, E> {
         self
     }
 
+    /// Fail if a pass binds two scratch fields sharing the same buffer, which would race.
+    fn check_scratch_aliasing(&self) -> Result<()> {
+        let scratch_ids = self
+            .scratch_buffers
+            .values()
+            .map(Buffer::id)
+            .collect::<HashSet<_>>();
+
+        for step in &self.steps {
+            let Step::ComputePass(compute_pass) = step else {
+                continue;
+            };
+            let mut bound = HashMap::<BufferId, &String>::default();
+            let names = compute_pass
+                .vars
+                .iter()
+                .chain(compute_pass.extra_groups.iter().flatten());
+            for name in names {
+                let Some(buffer) = self.buffers.get(name) else {
+                    continue;
+                };
+                if !scratch_ids.contains(&buffer.id()) {
+                    continue;
+                }
+                if let Some(other) = bound.insert(buffer.id(), name) {
+                    if other != name {
+                        return Err(Error::AliasedScratch(other.clone(), name.clone()));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Build an [`AppComputeWorker<W>`] from this builder.
+    /// Fails if a rotate step lists no buffer, if swapped or rotated buffers are listed twice
+    /// or don't all have the same size and usages, or if a binding size doesn't fit its buffer,
+    /// or if a pass binds two scratch fields of the same size.
+    pub fn try_build(&self) -> Result<AppComputeWorker<W>> {
+        for step in &self.steps {
+            match step {
+                Step::Swap(a, b) => check_swappable(&self.buffers, &[a, b])?,
+                Step::Rotate(names) => {
+                    check_swappable(&self.buffers, &names.iter().collect::<Vec<_>>())?
+                }
+                _ => {}
+            }
+        }
+
+        self.check_scratch_aliasing()?;
+
+        for step in &self.steps {
+            let Step::Clear(name) = step else {
+                continue;
+            };
+            let Some(buffer) = self.buffers.get(name) else {
+                return Err(Error::BufferNotFound(name.to_owned()));
+            };
+            if !buffer.usage().contains(BufferUsages::COPY_DST) {
+                return Err(Error::NotClearable(name.to_owned()));
+            }
+        }
+
+        for (name, size) in &self.binding_sizes {
+            let Some(buffer) = self.buffers.get(name) else {
+                return Err(Error::BufferNotFound(name.to_owned()));
+            };
+            if *size == 0 || *size > buffer.size() {
+                return Err(Error::InvalidBindingSize(name.to_owned(), *size));
+            }
+        }
+
+        Ok(AppComputeWorker::from(self))
+    }
+
     /// Build an [`AppComputeWorker<W>`] from this builder.
+    /// In case of error, this function will panic.
     pub fn build(&self) -> AppComputeWorker<W> {
-        AppComputeWorker::from(self)
+        self.try_build().unwrap()
     }
 }
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use bevy::render::render_resource::ShaderDefVal;
+use naga::{
+    valid::{Capabilities, ValidationFlags, Validator},
+    AddressSpace, Module,
+};
+use naga_oil::compose::{Composer, NagaModuleDescriptor, ShaderDefValue, ShaderType};
+
+/// Parse the WGSL `source` with `shader_defs` applied, as the pipeline cache does.
+///
+/// `None` if the shader can't be parsed on its own, e.g. because it relies on
+/// imports which are only resolved when the pipeline is created.
+pub(crate) fn parse_with_defs(source: &str, shader_defs: &[ShaderDefVal]) -> Option<Module> {
+    let shader_defs = shader_defs
+        .iter()
+        .map(|def| match def {
+            ShaderDefVal::Bool(name, value) => (name.clone(), ShaderDefValue::Bool(*value)),
+            ShaderDefVal::Int(name, value) => (name.clone(), ShaderDefValue::Int(*value)),
+            ShaderDefVal::UInt(name, value) => (name.clone(), ShaderDefValue::UInt(*value)),
+        })
+        .collect::<HashMap<_, _>>();
+
+    Composer::non_validating()
+        .make_naga_module(NagaModuleDescriptor {
+            source,
+            file_path: "",
+            shader_type: ShaderType::Wgsl,
+            shader_defs,
+            additional_imports: &[],
+        })
+        .ok()
+}
+
+/// The `@workgroup_size` of `entry_point` in `module`.
+pub(crate) fn declared_workgroup_size(module: &Module, entry_point: &str) -> Option<[u32; 3]> {
+    module
+        .entry_points
+        .iter()
+        .find(|entry| entry.name == entry_point)
+        .map(|entry| entry.workgroup_size)
+}
+
+/// The bytes of `var<workgroup>` memory used by `entry_point` in `module`.
+pub(crate) fn workgroup_storage_size(module: &Module, entry_point: &str) -> Option<u32> {
+    let info = Validator::new(ValidationFlags::empty(), Capabilities::all())
+        .validate(module)
+        .ok()?;
+
+    let index = module
+        .entry_points
+        .iter()
+        .position(|entry| entry.name == entry_point)?;
+    let function_info = info.get_entry_point(index);
+
+    let size = module
+        .global_variables
+        .iter()
+        .filter(|(handle, variable)| {
+            variable.space == AddressSpace::WorkGroup && !function_info[*handle].is_empty()
+        })
+        // Metal allocates threadgroup memory in 16 bytes blocks, be as strict as it is
+        .map(|(_, variable)| {
+            module.types[variable.ty]
+                .inner
+                .size(module.to_ctx())
+                .next_multiple_of(16)
+        })
+        .sum();
+    Some(size)
+}
@@ -0,0 +1,93 @@
+//! Run with `cargo test --features test_util`, needs a software adapter.
+
+use bevy::prelude::*;
+use bevy_app_compute::{
+    prelude::*,
+    test_util::{execute_now_blocking, finish_plugins, headless_app},
+};
+
+#[derive(TypePath)]
+struct SimpleShader;
+
+impl ComputeShader for SimpleShader {
+    fn shader() -> ShaderRef {
+        "shaders/simple.wgsl".into()
+    }
+}
+
+#[derive(ComputeFields, Clone, Copy)]
+enum Fields {
+    Uniform,
+    Values,
+}
+
+#[derive(Resource)]
+struct ImmediateWorker;
+
+impl ComputeWorker for ImmediateWorker {
+    type Fields = Fields;
+
+    fn build(app: &mut App) -> AppComputeWorker<Self> {
+        AppComputeWorkerBuilder::new(app)
+            .add_uniform(Fields::Uniform, &1.)
+            .add_staging(Fields::Values, &[0., 10., 20., 30.])
+            .add_pass::<SimpleShader>([4, 1, 1], &[Fields::Uniform, Fields::Values])
+            .immediate()
+            .build()
+    }
+}
+
+#[test]
+fn execute_now_runs_every_call() {
+    let mut app = headless_app();
+    app.add_plugins(AppComputeWorkerPlugin::<ImmediateWorker>::default());
+
+    let mut previous = vec![0., 10., 20., 30.];
+    for _ in 0..10 {
+        assert!(execute_now_blocking::<ImmediateWorker>(&mut app, 100));
+
+        let values: Vec<f32> = app
+            .world
+            .resource::<AppComputeWorker<ImmediateWorker>>()
+            .read_vec(Fields::Values);
+        for (value, before) in values.iter().zip(&previous) {
+            assert!(
+                value > before,
+                "{values:?} didn't increase from {previous:?}"
+            );
+        }
+        previous = values;
+    }
+}
+
+#[derive(Resource)]
+struct ContinuousWorker;
+
+impl ComputeWorker for ContinuousWorker {
+    type Fields = Fields;
+
+    fn build(app: &mut App) -> AppComputeWorker<Self> {
+        AppComputeWorkerBuilder::new(app)
+            .add_uniform(Fields::Uniform, &1.)
+            .add_staging(Fields::Values, &[0., 10., 20., 30.])
+            .add_pass::<SimpleShader>([4, 1, 1], &[Fields::Uniform, Fields::Values])
+            .build()
+    }
+}
+
+#[test]
+fn execute_now_needs_immediate_worker() {
+    let mut app = headless_app();
+    app.add_plugins(AppComputeWorkerPlugin::<ContinuousWorker>::default());
+    finish_plugins(&mut app);
+
+    let result = app.world.resource_scope(
+        |world, mut worker: Mut<AppComputeWorker<ContinuousWorker>>| {
+            worker.try_execute_now(world.resource::<AppPipelineCache>())
+        },
+    );
+    assert!(matches!(
+        result,
+        Err(Error::WrongRunMode(RunMode::Continuous))
+    ));
+}
@@ -0,0 +1,51 @@
+//! Run with `cargo test --features test_util`, needs a software adapter.
+
+use bevy::prelude::*;
+use bevy_app_compute::{
+    prelude::*,
+    test_util::{headless_app, step_blocking},
+};
+
+#[derive(TypePath)]
+struct IndirectShader;
+
+impl ComputeShader for IndirectShader {
+    fn shader() -> ShaderRef {
+        "shaders/indirect.wgsl".into()
+    }
+}
+
+#[derive(ComputeFields, Clone, Copy)]
+enum Fields {
+    Args,
+    Values,
+}
+
+#[derive(Resource)]
+struct IndirectWorker;
+
+impl ComputeWorker for IndirectWorker {
+    type Fields = Fields;
+
+    fn build(app: &mut App) -> AppComputeWorker<Self> {
+        AppComputeWorkerBuilder::new(app)
+            .add_indirect_storage(Fields::Args, &[[2, 1, 1]])
+            .add_staging(Fields::Values, &[0u32; 4])
+            .add_pass_indirect::<IndirectShader>(Fields::Args, 0, &[Fields::Values])
+            .build()
+    }
+}
+
+#[test]
+fn dispatch_from_indirect_args() {
+    let mut app = headless_app();
+    app.add_plugins(AppComputeWorkerPlugin::<IndirectWorker>::default());
+    assert!(step_blocking::<IndirectWorker>(&mut app, 100));
+
+    // Only the 2 workgroups of the args ran, one invocation each
+    let values: Vec<u32> = app
+        .world
+        .resource::<AppComputeWorker<IndirectWorker>>()
+        .read_vec(Fields::Values);
+    assert_eq!(values, [1, 1, 0, 0]);
+}
@@ -0,0 +1,63 @@
+//! Run with `cargo test --features test_util`, needs a software adapter.
+
+use bevy::prelude::*;
+use bevy_app_compute::{
+    prelude::*,
+    test_util::{headless_app, step_blocking},
+};
+
+#[derive(TypePath)]
+struct FirstPassShader;
+
+impl ComputeShader for FirstPassShader {
+    fn shader() -> ShaderRef {
+        "shaders/first_pass.wgsl".into()
+    }
+}
+
+#[derive(TypePath)]
+struct SecondPassShader;
+
+impl ComputeShader for SecondPassShader {
+    fn shader() -> ShaderRef {
+        "shaders/second_pass.wgsl".into()
+    }
+}
+
+#[derive(ComputeFields, Clone, Copy)]
+enum Fields {
+    Value,
+    Input,
+    Output,
+}
+
+#[derive(Resource)]
+struct MultiPassWorker;
+
+impl ComputeWorker for MultiPassWorker {
+    type Fields = Fields;
+
+    fn build(app: &mut App) -> AppComputeWorker<Self> {
+        AppComputeWorkerBuilder::new(app)
+            .add_uniform(Fields::Value, &3.)
+            .add_storage(Fields::Input, &[1., 2., 3., 4.])
+            .add_staging(Fields::Output, &[0f32; 4])
+            .add_pass::<FirstPassShader>([4, 1, 1], &[Fields::Value, Fields::Input, Fields::Output])
+            .add_pass::<SecondPassShader>([4, 1, 1], &[Fields::Output])
+            .build()
+    }
+}
+
+#[test]
+fn second_pass_reads_first_pass_writes() {
+    let mut app = headless_app();
+    app.add_plugins(AppComputeWorkerPlugin::<MultiPassWorker>::default());
+    assert!(step_blocking::<MultiPassWorker>(&mut app, 100));
+
+    // The second pass squares what the first one wrote in the same run
+    let output: Vec<f32> = app
+        .world
+        .resource::<AppComputeWorker<MultiPassWorker>>()
+        .read_vec(Fields::Output);
+    assert_eq!(output, [16., 25., 36., 49.]);
+}
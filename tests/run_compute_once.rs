@@ -0,0 +1,26 @@
+//! Run with `cargo test --features test_util`, needs a software adapter.
+
+use bevy::render::renderer::{RenderDevice, RenderQueue};
+use bevy_app_compute::{
+    prelude::*,
+    test_util::{finish_plugins, headless_app},
+};
+
+#[test]
+fn shader_compile_errors_are_returned() {
+    let mut app = headless_app();
+    finish_plugins(&mut app);
+
+    let render_device = app.world.resource::<RenderDevice>();
+    let render_queue = app.world.resource::<RenderQueue>();
+    let result = try_run_compute_once(
+        render_device,
+        render_queue,
+        "@compute @workgroup_size(1) fn main() { let x: u32 = undefined_name; }",
+        &[("values", &[0; 16])],
+        [1, 1, 1],
+        "values",
+    );
+
+    assert!(matches!(result, Err(Error::PipelineFailed(_))));
+}